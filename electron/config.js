@@ -0,0 +1,78 @@
+/**
+ * aicodeswitch.conf / 配置档的解析与合并。不依赖 electron，可在普通 Node 下用 node:test 测试
+ * （electron/test/config.test.js）；配置档的选择、警告的推送与来源记录仍在 main.js。
+ */
+
+'use strict';
+
+const fs = require('fs');
+const path = require('path');
+
+// 返回给渲染层 / 写入日志的配置副本：鉴权码、密钥类与 ENV_* 的值一律打码
+const SECRET_KEY_PATTERN = /AUTH|TOKEN|SECRET|PASSWORD|KEY/i;
+
+function redactConfig(config) {
+  const redacted = {};
+  for (const [key, value] of Object.entries(config)) {
+    redacted[key] = value && (SECRET_KEY_PATTERN.test(key) || key.startsWith('ENV_')) ? '******' : value;
+  }
+  return redacted;
+}
+
+// KEY=VALUE 格式，# 开头为注释。返回 { values, counts }，counts 记录每个键出现的次数
+function parseConfig(content) {
+  const values = {};
+  const counts = {};
+  // Windows 记事本保存的 UTF-8 带 BOM、CRLF 换行：显式去掉开头的 BOM，按 \r?\n 分行，保证首行的键能被识别
+  for (const line of String(content).replace(/^\uFEFF/, '').split(/\r?\n/)) {
+    const trimmed = line.trim();
+    if (!trimmed || trimmed.startsWith('#')) continue;
+    const eq = trimmed.indexOf('=');
+    if (eq <= 0) continue;
+    const key = trimmed.slice(0, eq).trim();
+    let value = trimmed.slice(eq + 1).trim();
+    // 与 dotenv 一致：去掉成对包裹的引号
+    if (value.length >= 2 && /^(['"]).*\1$/.test(value)) value = value.slice(1, -1);
+    values[key] = value;
+    counts[key] = (counts[key] || 0) + 1;
+  }
+  return { values, counts };
+}
+
+// 文件不存在或读取失败时返回空配置。重复出现的键以最后一次为准（与服务端 dotenv 的解析结果一致），
+// 重复项以打码后的值追加到 warnings
+function readConfigFile(filePath, warnings = []) {
+  let content;
+  try {
+    if (!fs.existsSync(filePath)) return {};
+    content = fs.readFileSync(filePath, 'utf-8');
+  } catch {
+    return {};
+  }
+  const { values, counts } = parseConfig(content);
+  for (const [key, count] of Object.entries(counts)) {
+    if (count > 1) {
+      const shown = redactConfig({ [key]: values[key] })[key];
+      warnings.push(`配置项 ${key} 在 ${path.basename(filePath)} 中出现了 ${count} 次，以最后一次为准: ${key}=${shown}`);
+    }
+  }
+  return values;
+}
+
+// 取 env 中非空的指定键（去掉首尾空白）
+function pickEnvOverrides(env, keys) {
+  return Object.fromEntries(keys.filter((key) => (env[key] || '').trim()).map((key) => [key, env[key].trim()]));
+}
+
+// 优先级：环境变量 > 配置档 > aicodeswitch.conf；未出现的键由调用方按默认值处理
+function mergeConfig(fileValues, profileValues, envOverrides) {
+  return Object.assign({}, fileValues, profileValues || {}, envOverrides || {});
+}
+
+module.exports = {
+  redactConfig,
+  parseConfig,
+  readConfigFile,
+  pickEnvOverrides,
+  mergeConfig,
+};
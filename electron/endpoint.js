@@ -0,0 +1,72 @@
+/**
+ * 服务地址推导与 URL 拼接。不依赖 electron 与运行期状态，可在普通 Node 下用 node:test 测试
+ * （electron/test/endpoint.test.js）；main.js 中的 serverEndpoint() 只负责填入当前的连接地址、端口与 BASE_PATH。
+ */
+
+'use strict';
+
+const net = require('net');
+
+// 与服务端 main.ts 保持一致：AUTH 开启时监听 0.0.0.0，否则仅监听 127.0.0.1。
+function resolveBindHost(config, env = process.env) {
+  return (env.AUTH || config.AUTH || '').trim() ? '0.0.0.0' : '127.0.0.1';
+}
+
+// 通配监听地址不能作为连接目标：0.0.0.0 → 127.0.0.1，:: → ::1；其余地址原样使用
+function toConnectHost(bindHost) {
+  if (!bindHost || bindHost === '0.0.0.0') return '127.0.0.1';
+  if (bindHost === '::' || bindHost === '[::]') return '::1';
+  return bindHost.replace(/^\[(.*)\]$/, '$1');
+}
+
+// 本机地址：127.0.0.0/8、::1、localhost（IPv6 可带方括号）
+function isLoopbackHost(host) {
+  const h = String(host || '').replace(/^\[(.*)\]$/, '$1').toLowerCase();
+  if (h === 'localhost' || h === '::1') return true;
+  return net.isIPv4(h) && h.startsWith('127.');
+}
+
+// URL 中的 IPv6 地址需要方括号包裹：::1 → [::1]
+function formatUrlHost(host) {
+  return net.isIPv6(host) ? `[${host}]` : host;
+}
+
+// 规范化 URL 路径配置：去掉首尾多余斜杠，空值返回 ''，否则返回 '/a/b' 形式
+function normalizeUrlPath(value) {
+  const trimmed = String(value || '').trim().replace(/^\/+|\/+$/g, '');
+  return trimmed ? `/${trimmed}` : '';
+}
+
+// 健康检查、关闭 / 登录请求与导航共用的 { scheme, host, port, basePath }。
+// BASE_PATH 只加在导航地址上（健康检查与 API 路由都在服务根路径）
+function createEndpoint(host, port, basePath) {
+  return { scheme: 'http', host, port, basePath: normalizeUrlPath(basePath) };
+}
+
+// IPv4 / IPv6 / 主机名都能得到合法 URL
+function endpointUrl(endpoint, urlPath = '/') {
+  return `${endpoint.scheme}://${formatUrlHost(endpoint.host)}:${endpoint.port}${urlPath}`;
+}
+
+function navigationUrl(endpoint) {
+  return endpointUrl(endpoint, endpoint.basePath ? `${endpoint.basePath}/` : '/');
+}
+
+// 另一协议栈的回环地址：127.x ⇄ ::1；主机名（如 localhost）交给系统解析，不做替换
+function alternateLoopbackHost(host) {
+  if (host === '::1') return '127.0.0.1';
+  if (net.isIPv4(host) && host.startsWith('127.')) return '::1';
+  return null;
+}
+
+module.exports = {
+  resolveBindHost,
+  toConnectHost,
+  isLoopbackHost,
+  formatUrlHost,
+  normalizeUrlPath,
+  createEndpoint,
+  endpointUrl,
+  navigationUrl,
+  alternateLoopbackHost,
+};
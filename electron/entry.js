@@ -0,0 +1,26 @@
+/**
+ * 服务入口文件的类型检查。不依赖 electron，可在普通 Node 下用 node:test 测试（electron/test/entry.test.js）；
+ * 入口定位与 ENTRY_INVALID 错误仍在 main.js。
+ */
+
+'use strict';
+
+const fs = require('fs');
+const path = require('path');
+
+const SERVER_ENTRY_EXTENSIONS = ['.js', '.mjs', '.cjs'];
+
+// resolved 为已确认存在的路径；source 用于错误文案（如「SERVER_ENTRY 」「服务入口」）。
+// 入口可用时返回 null，否则返回说明原因的错误信息
+function serverEntryProblem(resolved, source) {
+  if (fs.statSync(resolved).isDirectory()) {
+    return `${source}指向的是目录而不是 JS 文件: ${resolved}\n请指向其中的入口文件（如 dist/server/main.js）。`;
+  }
+  const ext = path.extname(resolved).toLowerCase();
+  if (!SERVER_ENTRY_EXTENSIONS.includes(ext)) {
+    return `${source}不是 JS 文件（扩展名 ${ext || '无'}）: ${resolved}\n服务入口需为 ${SERVER_ENTRY_EXTENSIONS.join(' / ')} 文件。`;
+  }
+  return null;
+}
+
+module.exports = { SERVER_ENTRY_EXTENSIONS, serverEntryProblem };
@@ -0,0 +1,38 @@
+/**
+ * 用户目录解析。不依赖 electron，可在普通 Node 下用 node:test 注入环境变量测试（electron/test/home.test.js）。
+ */
+
+'use strict';
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+// 依次尝试 os.homedir()、HOME、USERPROFILE、HOMEDRIVE+HOMEPATH、Electron 的 home 路径：
+// 部分锁定的企业版 Windows 镜像只设置了 HOMEDRIVE/HOMEPATH。返回 { dir, source }，记录命中的来源便于排查；
+// 都不可用时返回 null——不退回临时目录，否则桌面端与按 os.homedir() 读写的服务端会用上两份数据目录。
+function resolveHomeDir({
+  env = process.env,
+  homedir = os.homedir,
+  electronHome = () => '',
+  exists = fs.existsSync,
+} = {}) {
+  const sources = [
+    ['os.homedir()', () => homedir()],
+    ['HOME', () => env.HOME],
+    ['USERPROFILE', () => env.USERPROFILE],
+    ['HOMEDRIVE+HOMEPATH', () => (env.HOMEDRIVE && env.HOMEPATH
+      ? path.join(env.HOMEDRIVE, env.HOMEPATH)
+      : '')],
+    ["app.getPath('home')", () => electronHome()],
+  ];
+  for (const [source, getDir] of sources) {
+    try {
+      const dir = getDir();
+      if (dir && exists(dir)) return { dir, source };
+    } catch { /* try next */ }
+  }
+  return null;
+}
+
+module.exports = { resolveHomeDir };
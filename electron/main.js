@@ -13,162 +13,1825 @@
 
 'use strict';
 
-const { app, BrowserWindow, ipcMain } = require('electron');
+const { app, BrowserWindow, ipcMain, Tray, Menu, nativeImage, dialog, shell, screen, clipboard } = require('electron');
 const path = require('path');
 const fs = require('fs');
 const http = require('http');
+const net = require('net');
 const os = require('os');
+const { fileURLToPath } = require('url');
+const { resolveHomeDir } = require('./home');
+const { serverEntryProblem } = require('./entry');
+const { readConfigFile, redactConfig, pickEnvOverrides, mergeConfig } = require('./config');
+const {
+  resolveBindHost,
+  toConnectHost,
+  isLoopbackHost,
+  normalizeUrlPath,
+  createEndpoint,
+  endpointUrl,
+  navigationUrl,
+  alternateLoopbackHost,
+} = require('./endpoint');
+const { createZip } = require('./zip');
+
+// ── 用户目录解析 ──────────────────────────────────────────────────────
+// 来源与顺序见 home.js。都不可用时无法定位数据目录 ~/.aicodeswitch，在创建任何窗口前报错退出
+// （app ready 之前即可调用 dialog.showErrorBox；Linux 无图形界面时改为输出到 stderr）。
+const HOME = resolveHomeDir({ electronHome: () => app.getPath('home') });
+if (!HOME) {
+  dialog.showErrorBox('AI Code Switch', '[APP_DIR_UNAVAILABLE] 无法确定用户主目录（os.homedir()、HOME、USERPROFILE、' +
+    'HOMEDRIVE+HOMEPATH 与系统 home 路径均不可用），因此无法定位数据目录 ~/.aicodeswitch。\n' +
+    '请检查当前用户的主目录及 HOME / USERPROFILE 环境变量后重新启动应用。');
+  process.exit(1);
+}
+// 内嵌服务按 os.homedir() 定位 ~/.aicodeswitch，而 os.homedir() 优先读取 HOME（Windows 为 USERPROFILE）：
+// 目录来自其它来源时写回该变量，保证桌面端与服务端使用同一个数据目录
+if (HOME.source !== 'os.homedir()') process.env[process.platform === 'win32' ? 'USERPROFILE' : 'HOME'] = HOME.dir;
 
 // ── 调试日志（与旧 Tauri 启动日志同路径，便于延续运维习惯） ───────────────
-const LOG_DIR = path.join(os.homedir(), '.aicodeswitch');
+const LOG_DIR = path.join(HOME.dir, '.aicodeswitch');
 const LOG_FILE = path.join(LOG_DIR, 'app-launch-debug.log');
 
-function appendLog(msg) {
+// 启动最早阶段确保数据目录 ~/.aicodeswitch 与 logs/ 存在（首次安装时后端尚未创建它们），
+// 之后的日志、配置模板、诊断报告都直接写入，不再各自创建目录
+function ensureAppDir() {
+  fs.mkdirSync(path.join(LOG_DIR, 'logs'), { recursive: true });
+  return LOG_DIR;
+}
+
+// 最近的启动日志（内存环形缓冲），用于生成启动失败诊断报告
+const RECENT_LOG_LIMIT = 200;
+const recentLogLines = [];
+
+// LOG_LEVEL=error|warn|info|debug（默认 info）：低于该级别的日志不写文件、不输出终端。
+// 诊断报告用的内存缓冲不受级别限制，保证出错时仍能看到完整上下文。
+const LOG_LEVELS = { error: 0, warn: 1, info: 2, debug: 3 };
+const DEFAULT_LOG_LEVEL = 'info';
+
+function currentLogLevel() {
+  const level = String(config.LOG_LEVEL || '').trim().toLowerCase();
+  return level in LOG_LEVELS ? level : DEFAULT_LOG_LEVEL;
+}
+
+// LOG_FORMAT=text|json（默认 text）：json 时日志文件（app-launch-debug.log 与 server.log）每行一个 JSON 对象
+// { ts, level, msg, phase?, source }，供日志聚合工具直接解析。source 区分桌面端自身（shell）与服务输出（server），
+// phase 为当前所处的启动阶段（见 markStartup），启动流程之外省略。内存缓冲与诊断报告仍用文本格式。
+let logPhase = null;
+let writingShellLog = false;
+
+function isJsonLogFormat() {
+  return String(config.LOG_FORMAT || '').trim().toLowerCase() === 'json';
+}
+
+function formatJsonLogLine(level, msg, source) {
+  const entry = { ts: new Date().toISOString(), level, msg };
+  if (logPhase) entry.phase = logPhase;
+  entry.source = source;
+  return JSON.stringify(entry);
+}
+
+function appendLog(msg, level = 'info') {
+  const ts = new Date().toISOString();
+  const line = `[${ts}] [${level.toUpperCase()}] ${msg}`;
+  recentLogLines.push(line);
+  if (recentLogLines.length > RECENT_LOG_LIMIT) recentLogLines.shift();
+  if (LOG_LEVELS[level] > LOG_LEVELS[currentLogLevel()]) return;
   try {
-    if (!fs.existsSync(LOG_DIR)) fs.mkdirSync(LOG_DIR, { recursive: true });
-    const ts = new Date().toISOString();
-    fs.appendFileSync(LOG_FILE, `[${ts}] ${msg}\n`);
+    fs.appendFileSync(LOG_FILE, `${isJsonLogFormat() ? formatJsonLogLine(level, String(msg), 'shell') : line}\n`);
   } catch { /* ignore */ }
-  // 同时输出到终端，方便调试
-  // eslint-disable-next-line no-console
-  console.log(msg);
+  // 同时输出到终端（并经输出捕获写入 server.log），方便调试；标记来源，json 格式下记为 shell
+  writingShellLog = true;
+  try {
+    // eslint-disable-next-line no-console
+    (level === 'error' ? console.error : console.log)(msg);
+  } finally {
+    writingShellLog = false;
+  }
 }
 
-// ── 端口读取（与 CLI/Rust 行为一致：从 ~/.aicodeswitch/aicodeswitch.conf 取 PORT） ──
-const DEFAULT_PORT = 4567;
+// ── 服务输出捕获 ──────────────────────────────────────────────────────
+// 内嵌服务的 console 输出与主进程共用 stdout/stderr；生产环境没有控制台，
+// 这里把两者 tee 一份到 ~/.aicodeswitch/logs/server.log，供 getLogs 与问题反馈使用。
+// 启动时超过上限则轮转为 server.log.1（仅保留一份历史）。LOG_FORMAT=json 时按行转成 JSON 再写入。
+const SERVER_LOG_FILE = path.join(LOG_DIR, 'logs', 'server.log');
+const SERVER_LOG_MAX_BYTES = 10 * 1024 * 1024;
+const TAIL_READ_BYTES = 1024 * 1024;
 
-function readPortFromConfig() {
+// LOG_TO_STDOUT：是否把捕获的输出同时回显到终端。未打包（开发）时默认开启，终端里直接能看到后端日志；
+// 打包版默认关闭（通常没有控制台），需要时显式设置 LOG_TO_STDOUT=true。写入 server.log 不受影响。
+function shouldEchoOutput() {
+  if (!String(config.LOG_TO_STDOUT || '').trim()) return !app.isPackaged;
+  return configFlag(config, 'LOG_TO_STDOUT');
+}
+
+function installOutputCapture() {
+  let stream;
   try {
-    const confPath = path.join(os.homedir(), '.aicodeswitch', 'aicodeswitch.conf');
-    if (!fs.existsSync(confPath)) return DEFAULT_PORT;
-    const content = fs.readFileSync(confPath, 'utf-8');
-    for (const line of content.split(/\r?\n/)) {
-      const trimmed = line.trim();
-      if (trimmed.startsWith('PORT=')) {
-        const n = parseInt(trimmed.slice('PORT='.length).trim(), 10);
-        if (Number.isFinite(n) && n > 0) return n;
+    if (fs.existsSync(SERVER_LOG_FILE) && fs.statSync(SERVER_LOG_FILE).size > SERVER_LOG_MAX_BYTES) {
+      fs.renameSync(SERVER_LOG_FILE, `${SERVER_LOG_FILE}.1`);
+    }
+    stream = fs.createWriteStream(SERVER_LOG_FILE, { flags: 'a' });
+    stream.on('error', () => { /* ignore */ });
+  } catch {
+    return;
+  }
+  // json 格式需要完整行：未以换行结尾的部分暂存，等下一次写入拼接
+  const partial = { info: '', error: '' };
+  const writeJsonLines = (level, chunk, encoding) => {
+    const text = partial[level] + (Buffer.isBuffer(chunk) ? chunk.toString('utf-8') : String(chunk));
+    const lines = text.split(/\r?\n/);
+    partial[level] = lines.pop();
+    const source = writingShellLog ? 'shell' : 'server';
+    for (const line of lines) {
+      if (line) stream.write(`${formatJsonLogLine(level, line, source)}\n`, encoding);
+    }
+  };
+  for (const [target, level] of [[process.stdout, 'info'], [process.stderr, 'error']]) {
+    const originalWrite = target.write.bind(target);
+    target.write = (chunk, encoding, callback) => {
+      if (isJsonLogFormat()) {
+        writeJsonLines(level, chunk, typeof encoding === 'string' ? encoding : undefined);
+      } else {
+        stream.write(chunk, typeof encoding === 'string' ? encoding : undefined);
+      }
+      streamServerOutput(level, chunk);
+      captureStartOutput(chunk);
+      if (!shouldEchoOutput()) {
+        const cb = typeof encoding === 'function' ? encoding : callback;
+        if (typeof cb === 'function') process.nextTick(cb);
+        return true;
+      }
+      try {
+        return originalWrite(chunk, encoding, callback);
+      } catch {
+        // Windows 打包版无控制台时 stdout 可能不可写，忽略即可（日志文件已写入）
+        return true;
+      }
+    };
+  }
+}
+
+// ── 实时日志推送 ──────────────────────────────────────────────────────
+// 渲染层经 setLogStreaming(true) 开启后，把捕获到的输出按行推送为 server-log 事件
+// （[{ level: 'info' | 'error', text, at }]，stdout 为 info、stderr 为 error）。
+// 每 250ms 合并发送一批，单批最多保留最近 500 行，避免高频输出冲垮 IPC；默认关闭，关闭即暂停。
+const LOG_STREAM_FLUSH_MS = 250;
+const LOG_STREAM_MAX_BATCH = 500;
+let logStreamEnabled = false;
+let logStreamBuffer = [];
+let logStreamTimer = null;
+const logStreamPartial = { info: '', error: '' };
+
+function streamServerOutput(level, chunk) {
+  if (!logStreamEnabled) return;
+  const text = logStreamPartial[level] + (Buffer.isBuffer(chunk) ? chunk.toString('utf-8') : String(chunk));
+  const lines = text.split(/\r?\n/);
+  logStreamPartial[level] = lines.pop();
+  const at = Date.now();
+  for (const line of lines) logStreamBuffer.push({ level, text: line, at });
+  if (logStreamBuffer.length > LOG_STREAM_MAX_BATCH) {
+    logStreamBuffer.splice(0, logStreamBuffer.length - LOG_STREAM_MAX_BATCH);
+  }
+  if (!logStreamTimer && logStreamBuffer.length > 0) {
+    logStreamTimer = setTimeout(flushLogStream, LOG_STREAM_FLUSH_MS);
+  }
+}
+
+function flushLogStream() {
+  logStreamTimer = null;
+  const batch = logStreamBuffer;
+  logStreamBuffer = [];
+  if (batch.length === 0 || !mainWindow || mainWindow.isDestroyed()) return;
+  mainWindow.webContents.send('server-log', batch);
+}
+
+function setLogStreaming(enabled) {
+  logStreamEnabled = !!enabled;
+  if (!logStreamEnabled) {
+    logStreamBuffer = [];
+    logStreamPartial.info = '';
+    logStreamPartial.error = '';
+  }
+  return logStreamEnabled;
+}
+
+// 读取文件末尾 lineCount 行（最多读取末尾 1MB），文件不存在返回空数组
+function tailFile(file, lineCount) {
+  if (!fs.existsSync(file)) return [];
+  const { size } = fs.statSync(file);
+  const length = Math.min(size, TAIL_READ_BYTES);
+  const buffer = Buffer.alloc(length);
+  const fd = fs.openSync(file, 'r');
+  try {
+    fs.readSync(fd, buffer, 0, length, size - length);
+  } finally {
+    fs.closeSync(fd);
+  }
+  const lines = buffer.toString('utf-8').split(/\r?\n/);
+  if (lines.length > 0 && lines[lines.length - 1] === '') lines.pop();
+  return lines.slice(-lineCount);
+}
+
+// ── 启动失败诊断报告（每次失败覆盖写，反映最近一次启动） ───────────────
+const STARTUP_REPORT_FILE = path.join(LOG_DIR, 'logs', 'startup-error.txt');
+const STARTUP_REPORT_TAIL_LINES = 40;
+
+function writeStartupReport(report) {
+  let serverOutput = [];
+  try { serverOutput = tailFile(SERVER_LOG_FILE, STARTUP_REPORT_TAIL_LINES); } catch { /* ignore */ }
+  const lines = [
+    'AI Code Switch 启动失败诊断报告',
+    `时间: ${new Date().toISOString()}`,
+    `应用版本: ${app.getVersion()}`,
+    `Electron: ${process.versions.electron} / Node: ${process.versions.node} / Chrome: ${process.versions.chrome}`,
+    `系统: ${process.platform} ${os.release()} (${process.arch}${app.runningUnderARM64Translation ? '，经 ARM64 转译运行' : ''})`,
+    `可执行文件: ${process.execPath}`,
+    `工作目录: ${process.cwd()}`,
+    `服务入口: ${serverEntryPath || '(未解析)'}`,
+    `配置端口: ${readPortFromConfig(config)} / 实际端口: ${port}`,
+    `服务状态: ${serverModule ? '已加载' : '未加载'} / ${serverReady ? '就绪' : '未就绪'}`,
+    '',
+    '── 错误信息 ──',
+    report,
+    '',
+    `── 最近 ${STARTUP_REPORT_TAIL_LINES} 行启动日志 ──`,
+    ...recentLogLines.slice(-STARTUP_REPORT_TAIL_LINES),
+    '',
+    `── 最近 ${STARTUP_REPORT_TAIL_LINES} 行服务输出（logs/server.log） ──`,
+    ...serverOutput,
+  ];
+  try {
+    fs.writeFileSync(STARTUP_REPORT_FILE, `${lines.join('\n')}\n`);
+    return STARTUP_REPORT_FILE;
+  } catch {
+    return null;
+  }
+}
+
+// ── 配置读取（与 CLI/服务端一致：~/.aicodeswitch/aicodeswitch.conf，KEY=VALUE 格式） ──
+const DEFAULT_PORT = 4567;
+const CONFIG_PATH = path.join(LOG_DIR, 'aicodeswitch.conf');
+
+// 重复出现的键以最后一次为准（与服务端 dotenv 的解析结果一致），并把重复项记录到 configWarnings，
+// 由 bootstrap / reloadConfig 推送到启动日志
+let configWarnings = [];
+
+// 配置档（profile）：环境变量 AICODESWITCH_PROFILE=<name> 或启动参数 --profile <name>（后者优先）
+// 选择 ~/.aicodeswitch/profiles/<name>.conf，其中的配置项覆盖 aicodeswitch.conf，未写的项沿用默认配置；
+// 名称非法或文件不存在时记录警告并只使用默认配置
+const PROFILES_DIR = path.join(LOG_DIR, 'profiles');
+let activeProfile = null;    // { name, path, values }
+
+function resolveProfileName() {
+  const idx = process.argv.findIndex((arg) => arg === '--profile' || arg.startsWith('--profile='));
+  if (idx < 0) return (process.env.AICODESWITCH_PROFILE || '').trim();
+  const arg = process.argv[idx];
+  return (arg.includes('=') ? arg.slice('--profile='.length) : (process.argv[idx + 1] || '')).trim();
+}
+
+function resolveProfile() {
+  const name = resolveProfileName();
+  if (!name) return null;
+  if (!/^[\w.-]+$/.test(name)) {
+    configWarnings.push(`配置档名称无效: ${name}（仅允许字母、数字、. _ -），使用默认配置`);
+    return null;
+  }
+  const file = path.join(PROFILES_DIR, `${name}.conf`);
+  if (!fs.existsSync(file)) {
+    configWarnings.push(`配置档 ${name} 不存在（${file}），使用默认配置`);
+    return null;
+  }
+  return { name, path: file };
+}
+
+// 环境变量覆盖：优先级 环境变量 > 配置档 > aicodeswitch.conf > 默认值，容器编排等场景无需挂载配置文件即可注入。
+// 取启动时的快照——之后壳层会自行写入 process.env.PORT 等，不能再当作用户的设置。
+// HOST 不在其列：服务端监听地址由 AUTH 决定，HOST 始终被忽略。
+const ENV_OVERRIDE_KEYS = ['PORT', 'AUTH'];
+const LAUNCH_ENV_OVERRIDES = pickEnvOverrides(process.env, ENV_OVERRIDE_KEYS);
+
+function readConfig() {
+  configWarnings = [];
+  const values = readConfigFile(CONFIG_PATH, configWarnings);
+  activeProfile = resolveProfile();
+  if (activeProfile) activeProfile.values = readConfigFile(activeProfile.path, configWarnings);
+  return mergeConfig(values, activeProfile && activeProfile.values, LAUNCH_ENV_OVERRIDES);
+}
+
+// 配置项的生效来源，用于启动日志与 getEffectiveConfig
+function configSource(key) {
+  if (key in LAUNCH_ENV_OVERRIDES) return 'env';
+  if (activeProfile && key in activeProfile.values) return 'profile';
+  return String(config[key] || '').trim() ? 'config' : 'default';
+}
+
+function logConfigSources() {
+  const labels = { env: '环境变量', profile: '配置档', config: '配置文件', default: '默认值' };
+  appendLog(`PORT=${readPortFromConfig(config)}（来源: ${labels[configSource('PORT')]}），` +
+    `AUTH ${String(config.AUTH || '').trim() ? '已开启' : '未开启'}（来源: ${labels[configSource('AUTH')]}）`);
+  if ((process.env.HOST || '').trim()) {
+    appendLog(`⚠ 环境变量 HOST=${process.env.HOST} 已忽略：服务监听地址由 AUTH 决定`, 'warn');
+  }
+}
+
+function reportConfigWarnings() {
+  for (const warning of configWarnings) sendLog(`⚠ ${warning}`);
+}
+
+// 配置文件不存在时写入的模板（所有配置项均以注释形式列出）
+const CONFIG_TEMPLATE = `# AI Code Switch 配置文件（KEY=VALUE，# 开头为注释）
+# 修改后可在界面中重新加载配置，或重启应用生效
+
+# 服务端口（默认 ${DEFAULT_PORT}）
+# PORT=${DEFAULT_PORT}
+
+# 管理界面鉴权码；设置后服务监听 0.0.0.0，可从局域网访问
+# AUTH=
+
+# ── 桌面端 ──
+# 端口被其它程序占用时自动改用空闲端口
+# AUTO_PORT=true
+# 判定「端口监听失败」的正则（匹配报错与服务输出；AUTO_PORT 下据此换端口重试）
+# BIND_FAILURE_PATTERN=EADDRINUSE|address already in use
+# Windows / Linux 关闭窗口时隐藏到托盘
+# CLOSE_TO_TRAY=true
+# 关闭窗口后应用与服务继续运行
+# KEEP_ALIVE_ON_CLOSE=true
+# 连接已手动启动的外部服务，而不是启动内嵌服务
+# EXTERNAL_SERVER=true
+# 退出时等待服务关闭的最长秒数
+# SHUTDOWN_TIMEOUT=10
+# 停止服务前先 POST 的关闭端点（配置 AUTH 时自动登录鉴权）
+# SHUTDOWN_ENDPOINT=/api/shutdown
+# 无人值守模式：不弹对话框（只写日志），启动失败时以退出码 1 退出
+# HEADLESS=true
+# 使用 AUTH 自动登录本机管理界面，免去再次输入鉴权码
+# AUTH_AUTOLOGIN=true
+# 把服务输出同时回显到终端（开发版默认开启，打包版默认关闭）
+# LOG_TO_STDOUT=true
+# 桌面端日志级别：error / warn / info / debug
+# LOG_LEVEL=info
+# 日志文件格式：text（默认）或 json（每行一个 JSON 对象，便于日志聚合工具解析）
+# LOG_FORMAT=text
+# 窗口标题与初始尺寸（配置尺寸后不再默认最大化）
+# WINDOW_TITLE=AI Code Switch
+# WINDOW_WIDTH=1200
+# WINDOW_HEIGHT=720
+# 窗口显示时机：immediate（默认，先显示启动屏）或 on_ready（管理界面加载完成后再显示）
+# SHOW_WINDOW=immediate
+# 运行期健康检查间隔秒数（0 关闭）与触发自动重启的连续失败次数
+# HEALTH_MONITOR_INTERVAL=15
+# HEALTH_MONITOR_FAILURES=3
+# 自动重启的最大退避秒数，以及重启后持续健康多少秒后清零重试计数
+# RESTART_BACKOFF_MAX=60
+# RESTART_RESET_WINDOW=300
+# 自定义服务入口（绝对路径，或相对应用根目录；默认 dist/server/main.js）
+# SERVER_ENTRY=dist/server/main.js
+# 服务工作目录（支持 ~、绝对路径与相对 ~/.aicodeswitch 的路径，不存在时自动创建；默认沿用启动时的工作目录）
+# WORKING_DIR=data
+# 设为 false 时不自动启动内嵌服务，仅在端口上已有服务时连接
+# AUTO_START=true
+# 管理界面所在子路径（默认根路径）与健康检查路径（默认 /health）
+# BASE_PATH=/app
+# HEALTH_PATH=/health
+# 允许 AIC_ELECTRON_DEV_SERVER 指向非本机地址（默认仅允许本机；内嵌服务只监听本机或 0.0.0.0，不受此项影响）
+# ALLOW_REMOTE=true
+# 等待服务就绪的健康检查次数（每次间隔 300ms，默认 100 ≈ 30 秒）
+# HEALTH_MAX_ATTEMPTS=100
+# 首次健康检查前等待的秒数（默认 0）
+# HEALTH_INITIAL_DELAY=0
+# 整个启动流程失败后自动重试的次数（默认 0，最多 5）
+# STARTUP_RETRIES=0
+# 自定义 HEALTH_PATH 不返回 AI Code Switch 标记时设为 false，任意 2xx 即视为就绪
+# VERIFY_HEALTH_MARKER=true
+# 内嵌服务的 NODE_ENV
+# NODE_ENV=production
+# Node 运行时参数，仅支持 --enable-source-maps 与 --stack-trace-limit=<n>（应用启动时生效，修改后需重启应用）
+# NODE_ARGS=--enable-source-maps
+# 以 ENV_ 为前缀的配置项作为环境变量传给服务（去掉前缀）
+# ENV_HTTPS_PROXY=http://127.0.0.1:7890
+`;
+
+function ensureConfigFile() {
+  if (!fs.existsSync(CONFIG_PATH)) {
+    fs.writeFileSync(CONFIG_PATH, CONFIG_TEMPLATE);
+    appendLog(`已创建配置文件模板: ${CONFIG_PATH}`);
+  }
+  return CONFIG_PATH;
+}
+
+function configFlag(config, key) {
+  return /^(1|true|yes|on)$/i.test(String(config[key] || '').trim());
+}
+
+function readPortFromConfig(config) {
+  const n = parseInt(config.PORT, 10);
+  return Number.isFinite(n) && n > 0 ? n : DEFAULT_PORT;
+}
+
+// ENV_ 前缀的配置项原样转发给内嵌服务的环境变量（去掉前缀），
+// 例如 ENV_OPENAI_BASE_URL=... → process.env.OPENAI_BASE_URL
+const EXTRA_ENV_PREFIX = 'ENV_';
+
+// 服务端直接从环境变量读取的配置项（PORT / NODE_ENV 由壳层在启动时单独写入，HOST 始终忽略）；
+// 其余配置只供桌面端使用，不写入服务端环境
+const SERVER_CONFIG_KEYS = ['AUTH', 'JWT_SECRET', 'SKILLSMP_API_KEY'];
+// 启动时的环境变量快照：配置中删除某项后恢复为启动时的值（没有则删除），而不是连用户环境里的同名变量一起删掉
+const LAUNCH_ENV = { ...process.env };
+
+function getExtraEnv(config) {
+  const env = {};
+  for (const [key, value] of Object.entries(config)) {
+    if (key.startsWith(EXTRA_ENV_PREFIX) && key.length > EXTRA_ENV_PREFIX.length) {
+      env[key.slice(EXTRA_ENV_PREFIX.length)] = value;
+    }
+  }
+  return env;
+}
+
+// NODE_ARGS：按 shell 规则拆分（支持单/双引号包裹含空格的参数）
+function splitArgs(input) {
+  const args = [];
+  let current = '';
+  let quote = null;
+  let hasToken = false;
+  for (const ch of String(input || '')) {
+    if (quote) {
+      if (ch === quote) quote = null;
+      else current += ch;
+    } else if (ch === '"' || ch === "'") {
+      quote = ch;
+      hasToken = true;
+    } else if (/\s/.test(ch)) {
+      if (hasToken) {
+        args.push(current);
+        current = '';
+        hasToken = false;
       }
+    } else {
+      current += ch;
+      hasToken = true;
     }
-  } catch { /* ignore */ }
-  return DEFAULT_PORT;
+  }
+  if (hasToken) args.push(current);
+  return args;
+}
+
+// 内嵌服务与 Electron 主进程共用同一个 Node 运行时，无法像子进程那样传命令行参数；
+// V8 启动后再改 flag（v8.setFlagsFromString）的行为没有保证，因此只支持有运行时 API 的参数，
+// 在应用启动时应用一次（重新加载配置 / 重启服务不会重复应用），其余参数拒绝并记录警告。
+const NODE_ARG_HANDLERS = {
+  '--enable-source-maps': () => process.setSourceMapsEnabled(true),
+  '--stack-trace-limit': (value) => {
+    const n = Number(value);
+    if (value === undefined || !Number.isInteger(n) || n < 0) throw new Error('需要非负整数，例如 --stack-trace-limit=50');
+    Error.stackTraceLimit = n;
+  },
+};
+
+function applyNodeArgs(config) {
+  for (const arg of splitArgs(config.NODE_ARGS)) {
+    const eq = arg.indexOf('=');
+    const name = eq === -1 ? arg : arg.slice(0, eq);
+    const handler = NODE_ARG_HANDLERS[name];
+    if (!handler) {
+      appendLog(`⚠ NODE_ARGS 不支持的参数已忽略: ${arg}（仅支持 ${Object.keys(NODE_ARG_HANDLERS).join(' / ')}）`, 'warn');
+      continue;
+    }
+    try {
+      handler(eq === -1 ? undefined : arg.slice(eq + 1));
+      appendLog(`NODE_ARGS 已应用: ${arg}`);
+    } catch (err) {
+      appendLog(`⚠ NODE_ARGS 应用失败 ${arg}: ${err && err.message ? err.message : err}`, 'warn');
+    }
+  }
+}
+
+// ── 监听地址与连接地址 ────────────────────────────────────────────────
+// 地址推导与 URL 拼接见 endpoint.js；这里只保留依赖运行期配置与状态的部分。
+
+// 导航目标默认只允许指向本机（127.0.0.0/8、::1、localhost）。内嵌服务只绑定 127.0.0.1 / 0.0.0.0，
+// 健康检查地址总是本机；能指向其它机器的只有 AIC_ELECTRON_DEV_SERVER，防止它写错把桌面端
+// 指向远端。确需连接远端开发服务器时设置 ALLOW_REMOTE=true 显式放行（会记录日志）。
+function ensureLoopbackHost(host, purpose) {
+  if (isLoopbackHost(host)) return;
+  if (!configFlag(config, 'ALLOW_REMOTE')) {
+    throw new StartupError(
+      'REMOTE_HOST_REFUSED',
+      `${purpose}地址 ${host} 不是本机地址，已拒绝连接。\n` +
+      '如确需连接其它机器上的服务，请在 ~/.aicodeswitch/aicodeswitch.conf 中设置 ALLOW_REMOTE=true。',
+    );
+  }
+  appendLog(`⚠ ALLOW_REMOTE 已开启，${purpose}连接非本机地址: ${host}`, 'warn');
+}
+
+// 本机服务地址的唯一来源：健康检查、关闭 / 登录请求与导航都从这里拼接，
+// 避免「检查的是一处、打开的却是另一处」
+function serverEndpoint(host = connectHost(), targetPort = port) {
+  return createEndpoint(host, targetPort, config.BASE_PATH);
+}
+
+// ── 端口探测 ──────────────────────────────────────────────────────────
+function isPortFree(targetPort, host = bindHost) {
+  return new Promise((resolve) => {
+    const tester = net.createServer();
+    tester.once('error', () => resolve(false));
+    tester.once('listening', () => tester.close(() => resolve(true)));
+    tester.listen(targetPort, host);
+  });
+}
+
+// 由系统分配一个空闲端口（listen 0），拿到后立即释放
+function findFreePort(host = bindHost) {
+  return new Promise((resolve, reject) => {
+    const tester = net.createServer();
+    tester.once('error', reject);
+    tester.listen(0, host, () => {
+      const { port: freePort } = tester.address();
+      tester.close(() => resolve(freePort));
+    });
+  });
+}
+
+// ── 启动错误 ──────────────────────────────────────────────────────────
+// 带错误码的启动失败：message 面向用户，code 随 startup-error 一起发给渲染层，
+// 启动屏据此给出针对性指引，无需匹配文案。错误码：
+//   APP_DIR_UNAVAILABLE / ENTRY_NOT_FOUND / ENTRY_LOAD_FAILED / ENTRY_ACCESS_DENIED / ENTRY_INVALID /
+//   WORKING_DIR_INVALID /
+//   PORT_IN_USE_SELF / PORT_IN_USE_FOREIGN / PORT_AUTH_REJECTED /
+//   START_FAILED / READY_TIMEOUT / EXTERNAL_UNREACHABLE / WATCHDOG_TIMEOUT / NAVIGATION_FAILED /
+//   REMOTE_HOST_REFUSED / CANCELLED（启动期间应用退出，只记录日志不展示）
+// 其它未归类的异常统一为 UNKNOWN。
+class StartupError extends Error {
+  constructor(code, message) {
+    super(message);
+    this.name = 'StartupError';
+    this.code = code;
+  }
 }
 
 // ── 全局状态 ──────────────────────────────────────────────────────────
 let mainWindow = null;
+let config = {};             // aicodeswitch.conf 解析结果
 let serverModule = null;     // require('./dist/server/main.js') 的返回值
+let serverEntryPath = null;  // 已解析的服务入口路径（诊断报告用）
 let serverReady = false;
+let serverStarting = null;   // 进行中的 serverModule.start() Promise（不会 reject），退出时据此等待启动收尾
+let serverAttached = false;  // 当前连接的是外部服务（EXTERNAL_SERVER / AUTO_START=false），桌面端不负责停止它
+let hasNavigated = false;    // 仅在管理界面真正加载成功后置为 true
+let windowRevealed = false;  // 当前窗口是否已显示过（SHOW_WINDOW=on_ready 时延迟到导航成功）
+let windowMaximize = false;  // 首次显示时是否最大化（createWindow 中按配置/上次几何计算）
 let port = DEFAULT_PORT;
+let bindHost = '127.0.0.1'; // 服务端实际监听地址（由 AUTH 决定），健康检查与导航据此推导连接地址
+// 健康检查在推导出的地址连不上、另一协议栈的回环地址却有响应时（如外部服务只监听 ::1）记住后者，
+// 之后的健康检查、关闭请求与导航都改用它；每次重新推导监听地址时清空
+let activeConnectHost = null;
+
+function connectHost() {
+  return activeConnectHost || toConnectHost(bindHost);
+}
 let watchdogTimer = null;
 let isQuitting = false;
+let tray = null;
+
+// 启动屏状态：'loading' 加载中（此时发送的 IPC 消息会丢失，先排队）/ 'ready' 可接收消息 /
+// 'left' 已导航到管理界面（启动日志不再推送；出错时重新加载启动屏展示错误面板）/
+// 'failed' 启动屏本身加载失败（安装文件损坏等），错误改用系统对话框展示
+const SPLASH_FILE = path.join(__dirname, 'loading.html');
+let splashState = 'loading';
+let pendingSplashMessages = [];
+// 当前启动屏加载过程（不会 reject）：导航前等待它完成，避免慢机器上 WebView 尚未完成首次加载就被导航打断
+let splashLoading = Promise.resolve();
 
-const WATCHDOG_MS = 45000;   // 与旧 Tauri 看门狗一致：覆盖健康检查超时 + 宽限
+const WATCHDOG_MS = 45000;   // 与旧 Tauri 看门狗一致：覆盖健康检查超时 + 宽限（HEALTH_MAX_ATTEMPTS 调大时相应延长）
+const WATCHDOG_GRACE_MS = 15000;
+const NAVIGATE_ATTEMPTS = 3; // 冷启动时 WebView 偶发导航失败，短暂等待后重试
+const NAVIGATE_RETRY_DELAY_MS = 1000;
 
 // ── 健康检查 ──────────────────────────────────────────────────────────
-function checkHealth() {
+// 服务端 /health 返回 { status: 'ok', app: 'aicodeswitch' }，app 字段用于识别「是不是我们自己的服务」
+const HEALTH_MARKER = 'aicodeswitch';
+
+// 所有健康检查共用一个 keep-alive Agent：就绪等待与运行期监控的轮询复用连接，超时在此集中配置
+const HEALTH_TIMEOUT_MS = 1500;
+const healthAgent = new http.Agent({ keepAlive: true, maxSockets: 1 });
+
+// HEALTH_PATH：健康检查路径，与 BASE_PATH 无关。可用逗号分隔多个候选路径按顺序尝试，
+// 兼容不同版本后端的健康检查路由；未配置时只检查本服务提供的 /health
+// （/api/* 都经过鉴权中间件，默认探测这类路径只会得到 401，干扰对端口占用方的判断）
+const DEFAULT_HEALTH_PATHS = ['/health'];
+let lastHealthPath = null; // 最近一次返回 2xx 的路径，变化时记录日志
+
+function getHealthPaths() {
+  const paths = String(config.HEALTH_PATH || '').split(',').map(normalizeUrlPath).filter(Boolean);
+  return paths.length > 0 ? paths : DEFAULT_HEALTH_PATHS;
+}
+
+// 按顺序尝试候选路径，返回第一个 2xx 响应；都不是 2xx 时优先返回 401/403（要求鉴权）的响应，
+// 其次是最后一个响应；首个路径就连不上说明端口上没有服务，不再尝试其余路径
+async function requestHealth(targetPort) {
+  let fallback = null;
+  let first = true;
+  for (const healthPath of getHealthPaths()) {
+    // eslint-disable-next-line no-await-in-loop
+    let res = await requestHealthPath(targetPort, healthPath, connectHost());
+    // eslint-disable-next-line no-await-in-loop
+    if (!res && first) res = await requestHealthOtherStack(targetPort, healthPath);
+    first = false;
+    if (!res) return fallback;
+    if (res.statusCode >= 200 && res.statusCode < 300) {
+      if (healthPath !== lastHealthPath) {
+        lastHealthPath = healthPath;
+        appendLog(`健康检查路径: ${healthPath}`);
+      }
+      return res;
+    }
+    if (!isAuthRejected(fallback)) fallback = res;
+  }
+  return fallback;
+}
+
+// 主地址无响应时试另一协议栈的回环地址；仅对当前服务端口生效，有响应则切换连接地址
+async function requestHealthOtherStack(targetPort, healthPath) {
+  const alternate = targetPort === port ? alternateLoopbackHost(connectHost()) : null;
+  if (!alternate) return null;
+  const res = await requestHealthPath(targetPort, healthPath, alternate);
+  if (res) {
+    appendLog(`${connectHost()} 无响应，服务在 ${alternate} 上响应，改用该地址连接`);
+    activeConnectHost = alternate;
+  }
+  return res;
+}
+
+// 最近一次健康检查连接失败的原因（错误码 / 超时），供 debug 级别的逐次日志使用
+let lastHealthError = null;
+
+function requestHealthPath(targetPort, healthPath, host) {
   return new Promise((resolve) => {
     const req = http.get(
-      { hostname: '127.0.0.1', port, path: '/health', timeout: 1500 },
+      endpointUrl(serverEndpoint(host, targetPort), healthPath),
+      { timeout: HEALTH_TIMEOUT_MS, agent: healthAgent },
       (res) => {
-        res.resume();
-        resolve(res.statusCode >= 200 && res.statusCode < 300);
+        let body = '';
+        res.setEncoding('utf-8');
+        res.on('data', (chunk) => { if (body.length < 4096) body += chunk; });
+        res.on('end', () => resolve({ statusCode: res.statusCode, body, path: healthPath }));
+        res.on('error', () => resolve(null));
       },
     );
-    req.on('error', () => resolve(false));
-    req.on('timeout', () => { req.destroy(); resolve(false); });
+    req.on('error', (err) => {
+      lastHealthError = `${host} ${healthPath}: ${err.code || err.message}`;
+      resolve(null);
+    });
+    req.on('timeout', () => {
+      lastHealthError = `${host} ${healthPath}: 超时（${HEALTH_TIMEOUT_MS}ms）`;
+      req.destroy();
+      resolve(null);
+    });
   });
 }
 
-async function waitForServer() {
-  const deadline = Date.now() + 30000;
-  while (Date.now() < deadline) {
-    // 进程内服务启动失败可能直接 process.exit，这里轮询健康即可感知
+function isHealthy(res) {
+  return !!res && res.statusCode >= 200 && res.statusCode < 300;
+}
+
+// 本服务的 /health 不需要鉴权；/health 返回 401/403 说明端口上有服务在运行但要求凭证
+// （如反向代理保护的部署、AUTH 不一致的实例），与「没有服务」区分开单独提示。
+// 只认 /health 的响应：其它候选路径（如 /api/health）在本服务上本就要求鉴权，其 401 说明不了占用方是谁
+function isAuthRejected(res) {
+  return !!res && res.path === '/health' && (res.statusCode === 401 || res.statusCode === 403);
+}
+
+function isOwnHealthResponse(res) {
+  if (!res || res.statusCode < 200 || res.statusCode >= 300) return false;
+  try {
+    return JSON.parse(res.body).app === HEALTH_MARKER;
+  } catch {
+    return false;
+  }
+}
+
+// 只有带 AI Code Switch 标记（/health 返回 { app: 'aicodeswitch' }）的 2xx 才算本服务就绪：其它程序在同一端口
+// 对健康检查返回 200 时不能当成就绪，否则会把窗口导航到别人的页面。自定义 HEALTH_PATH 指向不返回标记的端点时，
+// 可设置 VERIFY_HEALTH_MARKER=false 放宽为任意 2xx
+function isServerResponse(res) {
+  if (/^(0|false|no|off)$/i.test(String(config.VERIFY_HEALTH_MARKER || '').trim())) return isHealthy(res);
+  return isOwnHealthResponse(res);
+}
+
+function foreignServerError(res) {
+  return new StartupError(
+    'PORT_IN_USE_FOREIGN',
+    `端口 ${port} 上的服务对健康检查返回了 HTTP ${res.statusCode}，但不是 AI Code Switch 服务（缺少健康检查标记），已拒绝连接。\n` +
+    '请关闭占用该端口的程序或在 ~/.aicodeswitch/aicodeswitch.conf 中更换 PORT=；' +
+    '若自定义的 HEALTH_PATH 本就不返回标记，可设置 VERIFY_HEALTH_MARKER=false。',
+  );
+}
+
+// 识别端口占用方：'free' 空闲 / 'self' 另一个 AI Code Switch 服务 /
+// 'protected' 有服务但 /health 要求鉴权 / 'foreign' 其它程序
+async function identifyPortOwner(targetPort) {
+  if (await isPortFree(targetPort)) return 'free';
+  const res = await requestHealth(targetPort);
+  if (isOwnHealthResponse(res)) return 'self';
+  return isAuthRejected(res) ? 'protected' : 'foreign';
+}
+
+// 就绪等待的检查次数：HEALTH_MAX_ATTEMPTS（默认 100 次 × 300ms 间隔 ≈ 30 秒），
+// 低配机器 / 冷启动容器可调大；限制在 1-2000 次（约 10 分钟）之间
+const HEALTH_RETRY_INTERVAL_MS = 300;
+const DEFAULT_HEALTH_MAX_ATTEMPTS = 100;
+const MAX_HEALTH_MAX_ATTEMPTS = 2000;
+
+function getHealthMaxAttempts() {
+  const n = parseInt(config.HEALTH_MAX_ATTEMPTS, 10);
+  if (!Number.isFinite(n)) return DEFAULT_HEALTH_MAX_ATTEMPTS;
+  return Math.min(Math.max(n, 1), MAX_HEALTH_MAX_ATTEMPTS);
+}
+
+// HEALTH_INITIAL_DELAY：首次健康检查前等待的秒数（默认 0，上限 300），已知启动较慢的环境可借此
+// 跳过注定失败的前几次检查，减少日志噪音；与检查次数 / 间隔相互独立，计入就绪等待总时长
+const MAX_HEALTH_INITIAL_DELAY_S = 300;
+
+function getHealthInitialDelayMs() {
+  const n = parseFloat(config.HEALTH_INITIAL_DELAY);
+  if (!Number.isFinite(n) || n <= 0) return 0;
+  return Math.min(n, MAX_HEALTH_INITIAL_DELAY_S) * 1000;
+}
+
+// 就绪等待的大致时长（秒），用于报错文案与看门狗
+function getHealthWaitSeconds() {
+  return Math.round((getHealthInitialDelayMs() + getHealthMaxAttempts() * HEALTH_RETRY_INTERVAL_MS) / 1000);
+}
+
+// 轮询 /health 直到就绪；返回 { ready, foreign, lastResponse }，lastResponse 供超时报错区分「无响应」与「要求鉴权」。
+// 返回 2xx 却没有本服务标记时立即结束（foreign=true），由调用方按「端口被其它程序占用」报错。
+// shouldAbort() 返回 true 时立即结束等待（如内嵌服务 start() 已失败），不必等满超时。
+// 每完成约 10% 的检查次数输出一次进度。
+async function waitForServer(shouldAbort = () => false) {
+  const maxAttempts = getHealthMaxAttempts();
+  const progressEvery = Math.max(1, Math.floor(maxAttempts / 10));
+  let lastResponse = null;
+  const initialDelayMs = getHealthInitialDelayMs();
+  if (initialDelayMs > 0) {
+    appendLog(`HEALTH_INITIAL_DELAY：${initialDelayMs / 1000} 秒后开始健康检查`);
+    // 分段等待，退出或启动失败时及时结束
+    const until = Date.now() + initialDelayMs;
+    while (Date.now() < until && !isQuitting && !shouldAbort()) {
+      // eslint-disable-next-line no-await-in-loop
+      await new Promise((r) => setTimeout(r, Math.min(HEALTH_RETRY_INTERVAL_MS, until - Date.now())));
+    }
+  }
+  // LOG_LEVEL=debug 时逐次记录每次检查的序号、耗时与结果（状态码或连接错误），排查偶发的启动慢 / 失败；
+  // 其它级别只保留上面的节流进度日志。非 debug 时不调用，避免刷掉诊断报告用的内存缓冲
+  const debug = currentLogLevel() === 'debug';
+  const startedAt = Date.now();
+  // 应用退出（关窗 / Cmd+Q / 信号）时立即结束等待，不让启动流程与退出流程互相等待
+  for (let attempt = 1; attempt <= maxAttempts && !isQuitting && !shouldAbort(); attempt++) {
+    lastHealthError = null;
     // eslint-disable-next-line no-await-in-loop
-    if (await checkHealth()) return true;
+    lastResponse = await requestHealth(port);
+    if (debug) {
+      const result = lastResponse ? `HTTP ${lastResponse.statusCode}` : `无响应（${lastHealthError || '未知错误'}）`;
+      appendLog(`健康检查 #${attempt}/${maxAttempts}（+${Date.now() - startedAt}ms）: ${result}`, 'debug');
+    }
+    if (isServerResponse(lastResponse)) return { ready: true, foreign: false, lastResponse };
+    if (isHealthy(lastResponse)) return { ready: false, foreign: true, lastResponse };
+    if (attempt % progressEvery === 0 && attempt < maxAttempts) {
+      appendLog(`等待服务就绪中（已检查 ${attempt}/${maxAttempts} 次）`);
+    }
     // eslint-disable-next-line no-await-in-loop
-    await new Promise((r) => setTimeout(r, 300));
+    await new Promise((r) => setTimeout(r, HEALTH_RETRY_INTERVAL_MS));
+  }
+  return { ready: false, foreign: false, lastResponse };
+}
+
+function authRejectedMessage(statusCode) {
+  return `端口 ${port} 上的服务在运行，但 /health 返回 HTTP ${statusCode}（要求鉴权）。\n` +
+    'AI Code Switch 自身的 /health 无需鉴权：可能是配置了不同 AUTH 的实例或前置了带鉴权的反向代理，请检查该端口上的服务。';
+}
+
+// ── 运行状态持久化（~/.aicodeswitch/state.json） ─────────────────────────
+// 记录 AUTO_PORT 最近一次实际使用的端口：下次启动配置端口仍被占用时优先复用它（若空闲），
+// 让自动换端口后的地址在重启间保持稳定。记录同时保存当时的配置端口，用户修改 PORT= 后自动失效。
+const STATE_FILE = path.join(LOG_DIR, 'state.json');
+
+function readState() {
+  try {
+    return JSON.parse(fs.readFileSync(STATE_FILE, 'utf-8')) || {};
+  } catch {
+    return {};
+  }
+}
+
+function writeState(patch) {
+  try {
+    const next = { ...readState(), ...patch };
+    for (const key of Object.keys(next)) {
+      if (next[key] === undefined) delete next[key];
+    }
+    fs.writeFileSync(STATE_FILE, `${JSON.stringify(next, null, 2)}\n`);
+  } catch (err) {
+    appendLog(`写入 state.json 失败: ${err && err.message ? err.message : err}`, 'warn');
+  }
+}
+
+function rememberAutoPort() {
+  const configuredPort = readPortFromConfig(config);
+  writeState(port === configuredPort
+    ? { autoPort: undefined }
+    : { autoPort: { configuredPort, port } });
+}
+
+async function pickAutoPort() {
+  const configuredPort = readPortFromConfig(config);
+  const { autoPort } = readState();
+  if (autoPort && autoPort.configuredPort === configuredPort && Number.isInteger(autoPort.port)) {
+    if (await isPortFree(autoPort.port)) return autoPort.port;
+  } else if (autoPort) {
+    writeState({ autoPort: undefined });
+  }
+  return findFreePort();
+}
+
+// 启动前确认端口可用，把「端口被占用」从 30 秒超时变成可操作的明确报错：
+//   - 被另一个 AI Code Switch 服务占用（如 CLI `aicos start`）：提示先停止它
+//   - 被其它程序占用：AUTO_PORT=true 时改用系统分配的空闲端口，否则提示更换 PORT=
+async function resolveServerPort() {
+  const owner = await identifyPortOwner(port);
+  if (owner === 'free') return;
+  if (owner === 'protected') {
+    throw new StartupError('PORT_AUTH_REJECTED', authRejectedMessage('401/403'));
+  }
+  if (owner === 'self') {
+    throw new StartupError('PORT_IN_USE_SELF',
+      `端口 ${port} 已被另一个 AI Code Switch 服务占用（可能是命令行 aicos 启动的服务）。\n` +
+      '请先执行 `aicos stop` 停止该服务，或在 ~/.aicodeswitch/aicodeswitch.conf 中设置其它 PORT=。',
+    );
+  }
+  if (configFlag(config, 'AUTO_PORT')) {
+    const freePort = await pickAutoPort();
+    sendLog(`端口 ${port} 已被其它程序占用，AUTO_PORT 已开启，改用空闲端口 ${freePort}`);
+    port = freePort;
+    return;
+  }
+  throw new StartupError(
+    'PORT_IN_USE_FOREIGN',
+    `端口 ${port} 已被其它应用程序占用。\n` +
+    '请关闭占用该端口的程序，或在 ~/.aicodeswitch/aicodeswitch.conf 中设置其它 PORT=' +
+    '（也可设置 AUTO_PORT=true 自动改用空闲端口）。',
+  );
+}
+
+// ── 向渲染层推送启动日志/错误 ─────────────────────────────────────────
+// HEADLESS=true：无人值守部署（kiosk、由外部进程管理器托管）不弹任何模态对话框，内容只写入日志；
+// 启动失败时以非 0 退出码退出，交给进程管理器决定是否重启
+let appExitCode = 0;
+
+function isHeadless() {
+  return configFlag(config, 'HEADLESS');
+}
+
+// 统一的消息框入口：HEADLESS 下记录日志并按「取消」处理
+function showMessageBox(options) {
+  if (isHeadless()) {
+    appendLog(`[HEADLESS] 跳过对话框「${options.message}」${options.detail ? `: ${options.detail}` : ''}`, options.type === 'warning' ? 'warn' : 'info');
+    return Promise.resolve({ response: options.cancelId !== undefined ? options.cancelId : 0 });
+  }
+  // SHOW_WINDOW=on_ready 时窗口可能仍隐藏：对话框需要可见的父窗口
+  revealMainWindow();
+  return mainWindow && !mainWindow.isDestroyed()
+    ? dialog.showMessageBox(mainWindow, options)
+    : dialog.showMessageBox(options);
+}
+
+// 启动屏无法展示错误面板时的兜底弹窗：提供「复制详情」（错误信息 + 诊断报告全文，便于反馈问题）
+// 与「打开日志目录」；app 未就绪时退回无按钮的 showErrorBox
+function showErrorDialog(message) {
+  if (isHeadless()) return; // 调用方已写入日志
+  if (!app.isReady()) {
+    try { dialog.showErrorBox('AI Code Switch 启动失败', message); } catch { /* 已写入日志 */ }
+    return;
+  }
+  showMessageBox({
+    type: 'error',
+    title: 'AI Code Switch',
+    message: 'AI Code Switch 启动失败',
+    detail: message,
+    buttons: ['确定', '复制详情', '打开日志目录'],
+    defaultId: 0,
+    cancelId: 0,
+  }).then(({ response }) => {
+    if (response === 1) {
+      let report = '';
+      try { report = fs.readFileSync(STARTUP_REPORT_FILE, 'utf-8'); } catch { /* 没有诊断报告 */ }
+      clipboard.writeText(report ? `${message}\n\n${report}` : message);
+    } else if (response === 2) {
+      return openLogsFolder();
+    }
+    return null;
+  }).catch((err) => appendLog(`错误弹窗异常: ${err && err.message ? err.message : err}`, 'warn'));
+}
+
+// 在文件管理器中打开 ~/.aicodeswitch（启动日志、logs/ 下的服务日志与诊断报告都在这里）
+async function openLogsFolder() {
+  const error = await shell.openPath(LOG_DIR);
+  if (error) throw new Error(`无法打开日志目录 ${LOG_DIR}: ${error}`);
+  return LOG_DIR;
+}
+
+function sendToSplash(channel, payload) {
+  if (!mainWindow || mainWindow.isDestroyed()) return;
+  if (splashState === 'failed') {
+    if (channel === 'startup-error') showErrorDialog(payload.message);
+  } else if (splashState === 'ready') {
+    mainWindow.webContents.send(channel, payload);
+  } else if (splashState === 'loading') {
+    pendingSplashMessages.push([channel, payload]);
+  }
+}
+
+function loadSplash() {
+  if (!mainWindow || mainWindow.isDestroyed()) return Promise.resolve();
+  splashState = 'loading';
+  const loading = mainWindow.loadFile(SPLASH_FILE).then(() => {
+    splashState = 'ready';
+    const queued = pendingSplashMessages;
+    pendingSplashMessages = [];
+    for (const [channel, payload] of queued) {
+      mainWindow.webContents.send(channel, payload);
+    }
+  }, (err) => {
+    // 被后续导航打断（ERR_ABORTED）不是加载失败
+    if (err && err.code === 'ERR_ABORTED') throw err;
+    // 启动屏无法加载时窗口只会是一片空白：改用系统错误对话框，已排队的错误一并展示
+    splashState = 'failed';
+    const queuedErrors = pendingSplashMessages.filter(([channel]) => channel === 'startup-error');
+    pendingSplashMessages = [];
+    showErrorDialog(
+      `无法加载启动界面，安装文件可能已损坏，请重新安装 AI Code Switch。\n\n` +
+      `${SPLASH_FILE}\n${err && err.message ? err.message : err}`,
+    );
+    for (const [, payload] of queuedErrors) showErrorDialog(payload.message);
+    throw err;
+  });
+  splashLoading = loading.catch(() => { /* 失败已在上面处理 */ });
+  return loading;
+}
+
+function sendLog(msg) {
+  appendLog(msg);
+  sendToSplash('startup-log', msg);
+}
+
+function sendError(error) {
+  const code = error instanceof StartupError ? error.code : 'UNKNOWN';
+  const message = error instanceof Error ? error.message : String(error);
+  appendLog(`✗ 启动失败 [${code}]:\n${message}`, 'error');
+  const reportPath = writeStartupReport(`[${code}] ${message}`);
+  const report = reportPath ? `${message}\n\n诊断报告已保存到: ${reportPath}` : message;
+  if (watchdogTimer) { clearTimeout(watchdogTimer); watchdogTimer = null; }
+  if (isHeadless()) {
+    appendLog(`[HEADLESS] 启动失败，以退出码 1 退出 [${code}]`, 'error');
+    appExitCode = 1;
+    app.quit();
+    return;
+  }
+  if (!mainWindow || mainWindow.isDestroyed()) return;
+  revealMainWindow();
+  // 已离开启动屏（导航后才失败）：回到启动屏展示错误面板，避免停留在空白/残缺页面
+  if (splashState === 'left') {
+    loadSplash().catch((err) => appendLog(`重新加载启动屏失败: ${err && err.message ? err.message : err}`, 'warn'));
+  }
+  const payload = { code, message: report };
+  // 服务在启动期间请求退出时附带退出码 / 信号，便于区分配置错误与被系统终止
+  if (error && error.exitCode !== undefined) {
+    payload.exitCode = error.exitCode;
+    payload.signal = error.signal;
+  }
+  sendToSplash('startup-error', payload);
+}
+
+// ── 服务生命周期事件 ──────────────────────────────────────────────────
+// 推送给当前页面（启动屏或管理界面），前端经 aicodeswitch.onServerStatus 订阅，无需轮询。
+// state: starting / ready / crashed / restarting / stopped
+// 非致命警告（架构转译、特权端口等）：写入启动日志，并以 runtime-warning 事件推送给当前页面
+// （{ kind, message, detected, recommended, at }），前端经 aicodeswitch.onRuntimeWarning 订阅后以横幅展示。
+// 启动屏阶段产生的警告在导航到管理界面后补发一次。
+const runtimeWarnings = [];
+
+function emitRuntimeWarning(kind, message, detected, recommended) {
+  const warning = { kind, message, detected, recommended, at: Date.now() };
+  // 重启服务时同一警告会再次产生，只保留一份用于补发
+  if (!runtimeWarnings.some((w) => w.kind === kind && w.message === message)) runtimeWarnings.push(warning);
+  sendLog(`⚠ ${message}`);
+  if (splashState === 'left' && mainWindow && !mainWindow.isDestroyed()) {
+    mainWindow.webContents.send('runtime-warning', warning);
+  }
+}
+
+function replayRuntimeWarnings() {
+  if (!mainWindow || mainWindow.isDestroyed()) return;
+  for (const warning of runtimeWarnings) mainWindow.webContents.send('runtime-warning', warning);
+}
+
+function emitServerStatus(state, extra = {}) {
+  appendLog(`服务状态: ${state}`);
+  if (!mainWindow || mainWindow.isDestroyed()) return;
+  mainWindow.webContents.send('server-status', { state, port, ...extra, at: Date.now() });
+}
+
+// ── 服务入口定位 ──────────────────────────────────────────────────────
+// 按优先级尝试各种布局，返回第一个存在的 dist/server/main.js：
+//   1. 基于 electron/main.js 自身的 __dirname 推导应用根（开发态显式文件入口 + 打包后 approot/electron/main.js）。
+//      首选它而非 app.getAppPath()：以 `electron electron/main.js` 启动时 getAppPath() 可能返回文件路径而非目录。
+//   2. app.getAppPath()（以目录 / package.json 方式启动）
+//   3. resources/app（打包后 asar: false 的标准布局）
+//   4. macOS .app bundle：Contents/MacOS/<exe> → Contents/Resources/app
+//   5. 可执行文件旁的 resources/app（部分 Windows / Linux 便携布局）
+// 配置 SERVER_ENTRY=（绝对路径，或相对应用根目录）时只使用该路径，不存在则直接报错，不回退默认布局。
+// 入口必须是 .js / .mjs / .cjs 文件（检查见 entry.js）：指向目录或其它类型文件时尽早给出明确错误，
+// 而不是等 require 抛出难懂的语法错误或一直等到就绪超时
+function validateServerEntry(resolved, source) {
+  const problem = serverEntryProblem(resolved, source);
+  if (problem) throw new StartupError('ENTRY_INVALID', problem);
+  return resolved;
+}
+
+// 兜底搜索：不同打包格式下 resourcesPath / execPath 的目录关系不尽相同，固定候选路径都找不到时，
+// 只在应用包内（process.resourcesPath 与 app.getAppPath()）向下有限深度地查找 dist/server/main.js
+// （跳过 node_modules 等大目录），绝不搜索安装目录之外的位置。找到的候选只记录并在错误中提示，
+// 不自动加载：入口会被 require 进拥有完整权限的主进程，必须由用户经 SERVER_ENTRY 明确指定
+const ENTRY_SEARCH_DEPTH = 4;
+const ENTRY_SEARCH_MAX_DIRS = 2000;
+const ENTRY_SEARCH_SKIP = new Set(['node_modules', '.git', 'locales', 'fs-db', 'data', 'backup']);
+
+function searchServerEntry(roots) {
+  let visited = 0;
+  const seen = new Set();
+  const queue = roots.map((dir) => ({ dir: path.resolve(dir), depth: 0 }));
+  while (queue.length > 0 && visited < ENTRY_SEARCH_MAX_DIRS) {
+    const { dir, depth } = queue.shift();
+    if (seen.has(dir)) continue;
+    seen.add(dir);
+    visited++;
+    const candidate = path.join(dir, 'dist', 'server', 'main.js');
+    if (fs.existsSync(candidate) && fs.statSync(candidate).isFile()) return candidate;
+    if (depth >= ENTRY_SEARCH_DEPTH) continue;
+    let entries = [];
+    try {
+      entries = fs.readdirSync(dir, { withFileTypes: true });
+    } catch {
+      continue;
+    }
+    for (const entry of entries) {
+      if (entry.isDirectory() && !ENTRY_SEARCH_SKIP.has(entry.name)) {
+        queue.push({ dir: path.join(dir, entry.name), depth: depth + 1 });
+      }
+    }
+  }
+  return null;
+}
+
+// WORKING_DIR：内嵌服务的工作目录（供按相对路径读写文件的后端使用），支持 ~ 与相对路径（相对 ~/.aicodeswitch，
+// 安装目录在打包版中通常只读且随升级替换），不存在时自动创建。服务在主进程内运行，因此通过 process.chdir 切换；未配置时保持启动时的工作目录
+const LAUNCH_CWD = process.cwd();
+
+function resolveWorkingDir() {
+  const raw = (config.WORKING_DIR || '').trim();
+  if (!raw) return null;
+  const expanded = /^~(?=$|[\\/])/.test(raw) ? path.join(HOME.dir, raw.slice(1)) : raw;
+  const resolved = path.resolve(LOG_DIR, expanded);
+  try {
+    fs.mkdirSync(resolved, { recursive: true });
+    if (!fs.statSync(resolved).isDirectory()) throw new Error('不是目录');
+  } catch (err) {
+    throw new StartupError(
+      'WORKING_DIR_INVALID',
+      `WORKING_DIR 指定的工作目录不可用: ${resolved}\n${err && err.message ? err.message : err}\n` +
+      '请检查 ~/.aicodeswitch/aicodeswitch.conf 中的 WORKING_DIR，或删除该配置使用默认工作目录。',
+    );
+  }
+  return resolved;
+}
+
+function applyWorkingDir() {
+  const workingDir = resolveWorkingDir() || LAUNCH_CWD;
+  if (workingDir === process.cwd()) return;
+  process.chdir(workingDir);
+  appendLog(`服务工作目录: ${workingDir}`);
+}
+
+function locateServerEntry() {
+  const customEntry = (config.SERVER_ENTRY || '').trim();
+  if (customEntry) {
+    const resolved = path.resolve(path.resolve(__dirname, '..'), customEntry);
+    if (fs.existsSync(resolved)) return validateServerEntry(resolved, 'SERVER_ENTRY ');
+    throw new StartupError(
+      'ENTRY_NOT_FOUND',
+      `SERVER_ENTRY 指定的服务入口不存在: ${resolved}\n` +
+      '请检查 ~/.aicodeswitch/aicodeswitch.conf 中的 SERVER_ENTRY（绝对路径，或相对应用根目录），或删除该配置使用默认入口。',
+    );
+  }
+  const rel = path.join('dist', 'server', 'main.js');
+  const exeDir = path.dirname(process.execPath);
+  const candidates = [
+    path.join(path.resolve(__dirname, '..'), rel),
+    path.join(app.getAppPath(), rel),
+    process.resourcesPath ? path.join(process.resourcesPath, 'app', rel) : null,
+    path.join(exeDir, '..', 'Resources', 'app', rel),
+    path.join(exeDir, 'resources', 'app', rel),
+  ];
+  const tried = [];
+  for (const candidate of candidates) {
+    if (!candidate) continue;
+    const resolved = path.resolve(candidate);
+    if (tried.includes(resolved)) continue;
+    tried.push(resolved);
+    if (fs.existsSync(resolved)) return validateServerEntry(resolved, '服务入口');
+  }
+  appendLog(`默认位置未找到服务入口，已尝试：\n${tried.map((p) => `  - ${p}`).join('\n')}`, 'warn');
+  const searchRoots = [process.resourcesPath, app.getAppPath()].filter((dir) => {
+    try { return !!dir && fs.statSync(dir).isDirectory(); } catch { return false; }
+  });
+  const found = searchServerEntry(searchRoots);
+  tried.push(...searchRoots.map((dir) => `${path.resolve(dir)}${path.sep}**${path.sep}${rel}（向下 ${ENTRY_SEARCH_DEPTH} 层）`));
+  let hint = '开发环境请先执行 `npm run build`（或 yarn build）生成 dist/server；安装版请重新安装应用。';
+  if (found) {
+    appendLog(`在应用包内找到候选服务入口（未自动加载）: ${found}`, 'warn');
+    hint = `在应用包内找到候选入口 ${found}，出于安全考虑未自动加载；确认无误后可在 ` +
+      `~/.aicodeswitch/aicodeswitch.conf 中设置 SERVER_ENTRY=${found}，否则请重新安装应用。`;
+  }
+  throw new StartupError(
+    'ENTRY_NOT_FOUND',
+    `Server entry file not found. 已尝试以下路径：\n${tried.map((p) => `  - ${p}`).join('\n')}\n${hint}`,
+  );
+}
+
+// ── 特权端口提示 ──────────────────────────────────────────────────────
+// PORT < 1024 是可预见的配置问题：Linux 非 root 无法监听；macOS 仅监听 127.0.0.1 之外的地址时可能受限；
+// Windows 可以监听但常与系统服务（IIS、HTTP.sys 等）冲突。启动前提示一次（不阻断），而不是等到超时才报笼统错误。
+function privilegedPortWarning(targetPort) {
+  if (targetPort >= 1024) return null;
+  const hint = `建议在 ~/.aicodeswitch/aicodeswitch.conf 中改用 1024 以上的端口（如默认的 ${DEFAULT_PORT}）。`;
+  if (process.platform === 'win32') {
+    return `端口 ${targetPort} 是系统保留端口，常被 IIS 等系统服务占用，可能无法启动。${hint}`;
+  }
+  if (process.platform === 'darwin') {
+    return `端口 ${targetPort} 是特权端口，部分系统版本或监听地址下需要管理员权限，可能无法启动。${hint}`;
+  }
+  return `端口 ${targetPort} 是特权端口，Linux 下非 root 用户（且未授予 CAP_NET_BIND_SERVICE）无法监听。${hint}`;
+}
+
+// 每个端口值只提示一次：托盘重启、自动恢复、重新加载配置等都会重新走启动流程，
+// 不应反复弹窗；配置改为另一个特权端口时再提示
+let privilegedPortWarned = null;
+
+function warnPrivilegedPort() {
+  const warning = privilegedPortWarning(port);
+  if (!warning || privilegedPortWarned === port) return;
+  privilegedPortWarned = port;
+  emitRuntimeWarning('privileged-port', warning, port, '>= 1024');
+  showMessageBox({ type: 'warning', title: 'AI Code Switch', message: '端口可能需要管理员权限', detail: warning })
+    .catch(() => { /* ignore */ });
+}
+
+// ── 连接外部已运行的服务 ──────────────────────────────────────────────
+// EXTERNAL_SERVER=true（配置或环境变量）：前端开发时后端由 `npm run dev:server` 手动运行，
+// 桌面壳不再加载内嵌服务，只等待该端口的 /health 就绪后导航；退出时也不关闭它。
+function isExternalServer() {
+  return configFlag(process.env, 'EXTERNAL_SERVER') || configFlag(config, 'EXTERNAL_SERVER');
+}
+
+async function attachExternalServer() {
+  bindHost = resolveBindHost(config);
+  activeConnectHost = null;
+  sendLog(`EXTERNAL_SERVER 已开启，等待外部服务（端口 ${port}）就绪...`);
+  emitServerStatus('starting', { external: true });
+  const { ready, foreign, lastResponse } = await waitForServer();
+  if (isQuitting) throw new StartupError('CANCELLED', '应用正在退出，已取消连接外部服务');
+  if (foreign) throw foreignServerError(lastResponse);
+  if (!ready) {
+    if (isAuthRejected(lastResponse)) throw new StartupError('PORT_AUTH_REJECTED', authRejectedMessage(lastResponse.statusCode));
+    throw new StartupError(
+      'EXTERNAL_UNREACHABLE',
+      `EXTERNAL_SERVER 已开启，但端口 ${port} 上的服务在约 ${getHealthWaitSeconds()} 秒内未就绪。\n` +
+      '请先手动启动后端（如 `npm run dev:server`），或去掉 EXTERNAL_SERVER 配置改由桌面端启动服务。',
+    );
+  }
+  serverReady = true;
+  serverAttached = true;
+  markStartup('ready');
+  sendLog('外部服务已就绪');
+  emitServerStatus('ready', { external: true });
+}
+
+// ── 加载服务入口（瞬时文件错误重试） ─────────────────────────────────
+// Windows 安装后首次启动时，杀毒软件扫描 / 文件锁可能让读取入口或依赖暂时失败（EBUSY/EPERM/EACCES），
+// 短暂等待后重试；文件确实不存在等其它错误重试无意义，直接抛出。
+const REQUIRE_ATTEMPTS = 3;
+const REQUIRE_RETRY_DELAY_MS = 1000;
+const TRANSIENT_FS_ERRORS = new Set(['EBUSY', 'EPERM', 'EACCES']);
+
+// 重试后仍是 EPERM / EACCES：多半是系统安全策略（macOS Gatekeeper 隔离属性、Windows SmartScreen /
+// Defender 拦截）或安装目录权限问题，按平台给出可操作的处理建议，而不是笼统的「加载失败」
+function accessDeniedError(target, err) {
+  let hint;
+  if (process.platform === 'darwin') {
+    hint = '可能被 macOS Gatekeeper 拦截：请在「系统设置 → 隐私与安全性」中允许 AI Code Switch 后重试；' +
+      '若从「下载」目录直接运行，请先将应用拖到「应用程序」文件夹。';
+  } else if (process.platform === 'win32') {
+    hint = '可能被 Windows SmartScreen / Defender 等安全软件拦截：请在安全软件中将 AI Code Switch 安装目录加入信任后重试，' +
+      '或以当前用户重新安装到有权限的目录。';
+  } else {
+    hint = '请检查应用安装目录的文件权限（当前用户需可读），或以当前用户重新安装。';
+  }
+  return new StartupError(
+    'ENTRY_ACCESS_DENIED',
+    `无权访问服务文件（${err.code}）: ${target}\n${hint}`,
+  );
+}
+
+async function requireServerEntry(serverEntry) {
+  for (let attempt = 1; ; attempt++) {
+    // 清理 require 缓存，避免开发态热重载时旧实例残留
+    try { delete require.cache[require.resolve(serverEntry)]; } catch { /* ignore */ }
+    try {
+      // eslint-disable-next-line global-require, import/no-dynamic-require
+      return require(serverEntry);
+    } catch (err) {
+      const transient = err && TRANSIENT_FS_ERRORS.has(err.code);
+      appendLog(`加载服务入口失败（第 ${attempt}/${REQUIRE_ATTEMPTS} 次，${err && err.code ? err.code : 'unknown'}）: ${err && err.message ? err.message : err}`, 'warn');
+      if (!transient || attempt >= REQUIRE_ATTEMPTS) throw err;
+      // eslint-disable-next-line no-await-in-loop
+      await new Promise((r) => setTimeout(r, REQUIRE_RETRY_DELAY_MS));
+    }
+  }
+}
+
+// start() 失败的报错附带服务最近的输出（server.log 末尾），通常包含具体原因
+const START_FAILURE_OUTPUT_LINES = 20;
+
+function startFailureError(err) {
+  let output = [];
+  try { output = tailFile(SERVER_LOG_FILE, START_FAILURE_OUTPUT_LINES); } catch { /* ignore */ }
+  const error = new StartupError(
+    'START_FAILED',
+    `服务启动异常：${err && err.message ? err.message : err}` +
+    (output.length > 0 ? `\n\n── 服务最近输出 ──\n${output.join('\n')}` : ''),
+  );
+  error.cause = err;
+  return error;
+}
+
+// 启动前的端口检查与服务真正 listen 之间端口仍可能被抢占。AUTO_PORT 开启时，监听失败
+// （错误码 EADDRINUSE，或报错 / 服务输出匹配 BIND_FAILURE_PATTERN）改用新的空闲端口重试，最多 3 次
+const PORT_RETRY_LIMIT = 3;
+const DEFAULT_BIND_FAILURE_PATTERN = /EADDRINUSE|address already in use|端口 \d+ .*仍被占用/i;
+
+function getBindFailurePattern() {
+  const raw = (config.BIND_FAILURE_PATTERN || '').trim();
+  if (!raw) return DEFAULT_BIND_FAILURE_PATTERN;
+  try {
+    return new RegExp(raw, 'i');
+  } catch (err) {
+    appendLog(`⚠ BIND_FAILURE_PATTERN 不是有效的正则表达式，使用默认规则: ${err.message}`, 'warn');
+    return DEFAULT_BIND_FAILURE_PATTERN;
+  }
+}
+
+// 本次 serverModule.start() 调用以来捕获的服务输出（最多保留末尾 64KB）。判断是否监听失败只看它与
+// start() 的异常本身：err.message 附带的 server.log 末尾可能是更早一次启动留下的端口占用报错
+const START_OUTPUT_MAX_CHARS = 64 * 1024;
+let startOutput = null;
+
+function captureStartOutput(chunk) {
+  if (startOutput === null) return;
+  startOutput += Buffer.isBuffer(chunk) ? chunk.toString('utf-8') : String(chunk);
+  if (startOutput.length > START_OUTPUT_MAX_CHARS) startOutput = startOutput.slice(-START_OUTPUT_MAX_CHARS);
+}
+
+function isBindFailure(err) {
+  if (!(err instanceof StartupError) || err.code !== 'START_FAILED') return false;
+  const cause = err.cause;
+  if (cause && cause.code === 'EADDRINUSE') return true;
+  const pattern = getBindFailurePattern();
+  return pattern.test(cause && cause.message ? cause.message : String(cause || '')) || pattern.test(startOutput || '');
+}
+
+// 内嵌服务与桌面端共用一个进程：服务（或其依赖、SERVER_ENTRY 指向的其它构建）在启动阶段调用
+// process.exit 或向自身发信号会直接带走整个应用，只留下一个消失的窗口。启动期间拦截这两种调用，
+// 记录退出码 / 信号并抛出异常中断服务的后续执行，由启动流程按启动失败报告。
+class ServerExitIntercepted extends Error {}
+
+function guardProcessExit() {
+  const guard = { exit: null };
+  const originalExit = process.exit;
+  const originalKill = process.kill;
+  process.exit = (code) => {
+    const exitCode = code === undefined ? (process.exitCode || 0) : code;
+    if (!guard.exit) guard.exit = { exitCode, signal: null };
+    appendLog(`✗ 服务在启动期间调用了 process.exit(${exitCode})，已拦截`, 'error');
+    throw new ServerExitIntercepted(`process.exit(${exitCode})`);
+  };
+  process.kill = (pid, signal = 'SIGTERM') => {
+    if (pid !== process.pid && pid !== 0) return originalKill.call(process, pid, signal);
+    if (!guard.exit) guard.exit = { exitCode: null, signal: String(signal) };
+    appendLog(`✗ 服务在启动期间向自身发送了 ${signal}，已拦截`, 'error');
+    throw new ServerExitIntercepted(`process.kill(${signal})`);
+  };
+  guard.restore = () => {
+    process.exit = originalExit;
+    process.kill = originalKill;
+  };
+  return guard;
+}
+
+function serverExitError({ exitCode, signal }) {
+  let output = [];
+  try { output = tailFile(SERVER_LOG_FILE, START_FAILURE_OUTPUT_LINES); } catch { /* ignore */ }
+  const how = signal ? `收到信号 ${signal}` : `退出码 ${exitCode}`;
+  const error = new StartupError(
+    'START_FAILED',
+    `服务在启动期间请求退出（${how}），通常是配置错误或初始化失败，请查看下方服务输出。` +
+    (output.length > 0 ? `\n\n── 服务最近输出 ──\n${output.join('\n')}` : ''),
+  );
+  error.exitCode = exitCode;
+  error.signal = signal;
+  return error;
+}
+
+// ── 在主进程内启动后端服务 ─────────────────────────────────────────────
+// 丢弃已加载的服务模块：先调用其 dispose() 注销 process 级监听器（uncaughtException / unhandledRejection /
+// SIGINT / SIGTERM）。每次启动都会清缓存重新 require 入口，不注销则监听器随重启叠加
+// （触发 MaxListenersExceededWarning），旧服务实例也一直被引用无法回收。旧版入口没有 dispose 时跳过
+function releaseServerModule() {
+  if (serverModule && typeof serverModule.dispose === 'function') {
+    try {
+      serverModule.dispose();
+    } catch (err) {
+      appendLog(`注销服务模块监听器失败: ${err && err.message ? err.message : err}`, 'warn');
+    }
+  }
+  serverModule = null;
+}
+
+async function startInProcessServer() {
+  for (let attempt = 1; ; attempt++) {
+    try {
+      // eslint-disable-next-line no-await-in-loop
+      return await startInProcessServerOnce();
+    } catch (err) {
+      if (attempt > PORT_RETRY_LIMIT || isQuitting || !configFlag(config, 'AUTO_PORT') || !isBindFailure(err)) throw err;
+      const failedPort = port;
+      // 失败的实例可能已初始化部分资源（数据库、工具配置），先走完关闭流程再重新加载入口
+      if (serverModule && typeof serverModule.gracefulShutdown === 'function') {
+        // eslint-disable-next-line no-await-in-loop
+        await shutdownWithTimeout(() => serverModule.gracefulShutdown('PORT_RETRY'));
+      }
+      releaseServerModule();
+      // eslint-disable-next-line no-await-in-loop
+      port = await findFreePort();
+      sendLog(`端口 ${failedPort} 监听失败（被占用），AUTO_PORT 已开启，改用端口 ${port} 重试（${attempt}/${PORT_RETRY_LIMIT}）`);
+    }
+  }
+}
+
+async function startInProcessServerOnce() {
+  // 开发态：通过环境变量 AIC_ELECTRON_DEV_SERVER 指向 vite dev server，UI 走热更新；
+  // 生产态：UI 由 Express 的 dist/ui 静态资源提供。
+  const isDev = !!process.env.AIC_ELECTRON_DEV_SERVER;
+
+  bindHost = resolveBindHost(config);
+  activeConnectHost = null;
+  const serverEntry = locateServerEntry();
+  serverEntryPath = serverEntry;
+  markStartup('locate');
+  appendLog(`Electron 主进程模式: ${isDev ? '开发' : '生产'}`);
+  appendLog(`服务入口: ${serverEntry}`);
+
+  warnPrivilegedPort();
+  await resolveServerPort();
+
+  applyWorkingDir();
+
+  // 先应用 ENV_* 自定义环境变量，再写入壳层控制的变量（后者优先，不允许被覆盖）
+  const extraEnv = getExtraEnv(config);
+  Object.assign(process.env, extraEnv);
+  // 服务端自行用 dotenv 读取默认 aicodeswitch.conf（不覆盖已有环境变量）：配置档中的项（如 AUTH）需先写入环境变量。
+  // 写入的是合并后的 config（环境变量 > 配置档 > 配置文件），而不是配置档原值，保证启动环境里的 AUTH 不被配置档覆盖
+  for (const key of SERVER_CONFIG_KEYS) setServerEnv(key, config[key]);
+
+  // 内嵌进程模式：服务端 shutdown 后不 process.exit，且被 require 时不自动 start
+  // NODE_ENV 默认 production，可在配置中改为 development 等以便调试；
+  // AICODESWITCH_SHELL_VERSION 告知服务端启动它的桌面端版本（兼容性判断 / 统计）
+  const shellEnv = {
+    AIC_IN_PROCESS: '1',
+    PORT: String(port),
+    NODE_ENV: (config.NODE_ENV || '').trim() || 'production',
+    AICODESWITCH_SHELL_VERSION: app.getVersion(),
+  };
+  Object.assign(process.env, shellEnv);
+
+  // 汇总一行启动参数，便于在终端手动复现（等价于 `node <入口>`，在同样的工作目录与环境变量下运行）
+  const auth = process.env.AUTH || config.AUTH;
+  const launchEnv = redactConfig({ ...extraEnv, ...shellEnv, ...(auth ? { AUTH: auth } : {}) });
+  appendLog(`启动参数: ${JSON.stringify({
+    runtime: `${process.execPath} (Node ${process.versions.node})`,
+    entry: serverEntry,
+    cwd: process.cwd(),
+    env: launchEnv,
+  })}`);
+
+  // 加载与启动期间拦截 process.exit / 自身信号，转为启动失败（附退出码）而不是带走整个桌面端
+  const exitGuard = guardProcessExit();
+  try {
+    try {
+      // 上一次启动失败后未经 stopInProcessServer 的实例同样先注销其监听器
+      releaseServerModule();
+      serverModule = await requireServerEntry(serverEntry);
+    } catch (err) {
+      if (exitGuard.exit) throw serverExitError(exitGuard.exit);
+      if (err && (err.code === 'EPERM' || err.code === 'EACCES')) throw accessDeniedError(err.path || serverEntry, err);
+      throw new StartupError('ENTRY_LOAD_FAILED', `加载服务入口失败：${err && err.message ? err.message : err}`);
+    }
+    markStartup('load');
+    if (!serverModule || typeof serverModule.start !== 'function') {
+      throw new StartupError('ENTRY_INVALID', '服务入口未导出 start() 函数，请检查 src/server/main.ts 的导出。');
+    }
+
+    // 进程内启动服务（异步）。内嵌模式下 start() 在监听成功后 resolve，启动期致命错误（端口占用、
+    // 初始化崩溃等）令其 reject：此时立即结束健康等待并附上最近的服务输出，而不是等满就绪超时
+    emitServerStatus('starting');
+    let startFailure = null;
+    startOutput = '';
+    serverStarting = serverModule.start().catch((err) => {
+      appendLog(`服务启动异常: ${err && err.stack ? err.stack : err}`, 'error');
+      emitServerStatus('crashed', { error: err && err.message ? err.message : String(err), code: 'START_FAILED' });
+      startFailure = err || new Error('unknown error');
+      // 已就绪后才失败（正常不会发生）不再经过下方的等待流程，直接报错
+      if (serverReady) sendError(startFailureError(err));
+    }).finally(() => { serverStarting = null; });
+
+    sendLog('正在等待服务就绪...');
+    const { ready, foreign, lastResponse } = await waitForServer(() => !!startFailure || !!exitGuard.exit);
+    if (isQuitting) throw new StartupError('CANCELLED', '应用正在退出，已取消启动');
+    if (exitGuard.exit) throw serverExitError(exitGuard.exit);
+    if (startFailure) throw startFailureError(startFailure);
+    if (foreign) throw foreignServerError(lastResponse);
+    if (!ready) {
+      if (isAuthRejected(lastResponse)) throw new StartupError('PORT_AUTH_REJECTED', authRejectedMessage(lastResponse.statusCode));
+      throw new StartupError(
+        'READY_TIMEOUT',
+        `服务在约 ${getHealthWaitSeconds()} 秒（${getHealthMaxAttempts()} 次健康检查）内未就绪（端口 ${port}）。` +
+        '低配机器可在 aicodeswitch.conf 中调大 HEALTH_MAX_ATTEMPTS=。详见 ~/.aicodeswitch/app-launch-debug.log',
+      );
+    }
+  } finally {
+    exitGuard.restore();
+  }
+  serverReady = true;
+  startOutput = null;
+  markStartup('ready');
+  if (configFlag(config, 'AUTO_PORT')) rememberAutoPort();
+  sendLog('服务已就绪');
+  emitServerStatus('ready');
+}
+
+// ── 运行期健康监控 ────────────────────────────────────────────────────
+// 服务就绪后每 HEALTH_MONITOR_INTERVAL 秒（默认 15，0 关闭）检查一次 /health，
+// 连续 HEALTH_MONITOR_FAILURES 次（默认 3）失败则自动重启服务并重新导航，避免长时间运行后界面失效。
+const DEFAULT_HEALTH_MONITOR_INTERVAL_S = 15;
+const DEFAULT_HEALTH_MONITOR_FAILURES = 3;
+let healthMonitorTimer = null;
+let healthFailures = 0;
+
+function readNonNegativeNumber(key, fallback) {
+  const n = parseFloat(config[key]);
+  return Number.isFinite(n) && n >= 0 ? n : fallback;
+}
+
+function startHealthMonitor() {
+  stopHealthMonitor();
+  const intervalS = readNonNegativeNumber('HEALTH_MONITOR_INTERVAL', DEFAULT_HEALTH_MONITOR_INTERVAL_S);
+  if (intervalS === 0) return;
+  healthMonitorTimer = setInterval(() => {
+    monitorHealth().catch((err) => appendLog(`健康监控异常: ${err && err.message ? err.message : err}`, 'warn'));
+  }, intervalS * 1000);
+}
+
+// 同时取消尚未执行的自动重启（用户从托盘停止服务 / 退出应用时不应再被拉起）
+function stopHealthMonitor() {
+  if (healthMonitorTimer) { clearInterval(healthMonitorTimer); healthMonitorTimer = null; }
+  if (recoveryTimer) { clearTimeout(recoveryTimer); recoveryTimer = null; }
+  healthFailures = 0;
+}
+
+async function monitorHealth() {
+  if (!serverReady || isQuitting) return;
+  if (isServerResponse(await requestHealth(port))) {
+    healthFailures = 0;
+    return;
+  }
+  const threshold = Math.max(1, Math.round(readNonNegativeNumber('HEALTH_MONITOR_FAILURES', DEFAULT_HEALTH_MONITOR_FAILURES)));
+  healthFailures += 1;
+  appendLog(`⚠ 运行期健康检查失败（连续 ${healthFailures}/${threshold} 次）`, 'warn');
+  if (healthFailures < threshold) return;
+
+  stopHealthMonitor();
+  emitServerStatus('crashed', { error: `健康检查连续 ${threshold} 次失败`, code: 'HEALTH_LOST' });
+  scheduleRecovery();
+}
+
+// 自动重启退避：2s 起指数增长，上限 RESTART_BACKOFF_MAX 秒（默认 60，小于 2s 按 2s 处理），叠加 ±25% 随机抖动，
+// 避免每次都撞上同一个短暂冲突（如端口刚被释放中）。重启后持续健康 RESTART_RESET_WINDOW 秒
+// （默认 300）才把重试计数清零；重启失败则按更长的退避继续重试。
+const RESTART_BACKOFF_BASE_MS = 2000;
+const DEFAULT_RESTART_BACKOFF_MAX_S = 60;
+const DEFAULT_RESTART_RESET_WINDOW_S = 300;
+let restartAttempts = 0;
+let recoveryTimer = null;
+let restartResetTimer = null;
+
+function restartDelayMs(attempt) {
+  // 上限不低于起始退避，否则 RESTART_BACKOFF_MAX=0 会让健康监控失败后立即反复重启
+  const capMs = Math.max(RESTART_BACKOFF_BASE_MS, readNonNegativeNumber('RESTART_BACKOFF_MAX', DEFAULT_RESTART_BACKOFF_MAX_S) * 1000);
+  const backoff = Math.min(capMs, RESTART_BACKOFF_BASE_MS * 2 ** (attempt - 1));
+  return Math.min(capMs, Math.round(backoff * (0.75 + Math.random() * 0.5)));
+}
+
+function scheduleRecovery() {
+  if (isQuitting) return;
+  restartAttempts += 1;
+  if (restartResetTimer) { clearTimeout(restartResetTimer); restartResetTimer = null; }
+  const delay = restartDelayMs(restartAttempts);
+  sendLog(`服务无响应，${(delay / 1000).toFixed(1)} 秒后自动重启（第 ${restartAttempts} 次）...`);
+  recoveryTimer = setTimeout(() => {
+    recoveryTimer = null;
+    runLifecycle('自动恢复服务', async () => {
+      // 等待期间服务已恢复（或用户已从托盘手动启动）则不再重启
+      if (isQuitting || (serverReady && isServerResponse(await requestHealth(port)))) return;
+      emitServerStatus('restarting');
+      await stopInProcessServer('HEALTH_RECOVERY');
+      await startAndNavigate();
+      if (!serverReady) {
+        scheduleRecovery();
+        return;
+      }
+      const windowMs = readNonNegativeNumber('RESTART_RESET_WINDOW', DEFAULT_RESTART_RESET_WINDOW_S) * 1000;
+      restartResetTimer = setTimeout(() => {
+        restartResetTimer = null;
+        if (serverReady) restartAttempts = 0;
+      }, windowMs);
+    });
+  }, delay);
+}
+
+// 服务关闭等待上限（SHUTDOWN_TIMEOUT 秒，默认 10）：gracefulShutdown 卡住时不拖住退出/重启流程
+const DEFAULT_SHUTDOWN_TIMEOUT_S = 10;
+
+function getShutdownTimeoutMs() {
+  const n = parseFloat(config.SHUTDOWN_TIMEOUT);
+  return (Number.isFinite(n) && n > 0 ? n : DEFAULT_SHUTDOWN_TIMEOUT_S) * 1000;
+}
+
+// 在超时内等待关闭完成，返回 true 表示正常关闭，false 表示超时后放弃等待
+async function shutdownWithTimeout(task) {
+  const timeoutMs = getShutdownTimeoutMs();
+  let timer = null;
+  const timedOut = new Promise((resolve) => { timer = setTimeout(() => resolve(false), timeoutMs); });
+  try {
+    const clean = await Promise.race([Promise.resolve().then(task).then(() => true), timedOut]);
+    appendLog(clean ? '服务已正常关闭' : `⚠ 服务在 ${timeoutMs / 1000} 秒内未完成关闭，放弃等待继续执行`, clean ? 'info' : 'warn');
+    return clean;
+  } finally {
+    clearTimeout(timer);
+  }
+}
+
+// SHUTDOWN_ENDPOINT：停止服务前先 POST 的关闭端点（如 /api/shutdown），让服务自行走完关闭流程
+const SHUTDOWN_REQUEST_TIMEOUT_MS = 5000;
+
+function getShutdownEndpoint() {
+  return normalizeUrlPath(config.SHUTDOWN_ENDPOINT);
+}
+
+// 向本机服务 POST JSON；返回 { statusCode, body }，连接失败或超时返回 null
+function postJson(urlPath, payload, headers = {}) {
+  return new Promise((resolve) => {
+    const data = JSON.stringify(payload || {});
+    const req = http.request(
+      endpointUrl(serverEndpoint(), urlPath),
+      {
+        method: 'POST',
+        timeout: SHUTDOWN_REQUEST_TIMEOUT_MS,
+        headers: { 'Content-Type': 'application/json', 'Content-Length': Buffer.byteLength(data), ...headers },
+      },
+      (res) => {
+        let body = '';
+        res.setEncoding('utf-8');
+        res.on('data', (chunk) => { if (body.length < 4096) body += chunk; });
+        res.on('end', () => resolve({ statusCode: res.statusCode, body }));
+      },
+    );
+    req.on('error', () => resolve(null));
+    req.on('timeout', () => { req.destroy(); resolve(null); });
+    req.end(data);
+  });
+}
+
+// 配置了 AUTH 时用授权码换取管理接口的 JWT（Access-Token 头）；失败返回 null
+async function requestAuthToken() {
+  const authCode = (process.env.AUTH || config.AUTH || '').trim();
+  if (!authCode) return null;
+  const res = await postJson('/api/auth/login', { authCode });
+  if (!res || res.statusCode !== 200) {
+    appendLog(`⚠ 使用 AUTH 登录失败（${res ? `HTTP ${res.statusCode}` : '无响应'}）`, 'warn');
+    return null;
+  }
+  try {
+    return JSON.parse(res.body).token || null;
+  } catch {
+    return null;
+  }
+}
+
+// 调用关闭端点，返回服务是否接受了关闭请求（2xx）
+async function postShutdownEndpoint(endpoint) {
+  const token = await requestAuthToken();
+  const res = await postJson(endpoint, {}, token ? { 'Access-Token': token } : {});
+  if (res && res.statusCode >= 200 && res.statusCode < 300) return true;
+  appendLog(`⚠ 关闭端点 ${endpoint} 调用失败（${res ? `HTTP ${res.statusCode}` : '无响应'}）`, 'warn');
+  return false;
+}
+
+// 关闭进程内服务：配置了 SHUTDOWN_ENDPOINT 且服务可达时先调用端点，再调用 gracefulShutdown。
+// 服务端的关闭流程可重入，端点已触发时 gracefulShutdown 只是等待同一流程完成。
+async function shutdownServerModule(reason, reachable) {
+  const endpoint = getShutdownEndpoint();
+  let method = 'gracefulShutdown()';
+  if (endpoint && reachable && await postShutdownEndpoint(endpoint)) {
+    method = `关闭端点 ${endpoint}`;
+  }
+  await serverModule.gracefulShutdown(reason);
+  appendLog(`服务关闭方式: ${method}`);
+}
+
+// 停止进程内服务（托盘「停止/重启服务」）：走完整 gracefulShutdown，窗口回到启动屏
+async function stopInProcessServer(reason) {
+  stopHealthMonitor();
+  if (!serverModule) {
+    if (serverAttached) {
+      // 外部服务由外部管理：只断开连接，不停止服务本身
+      sendLog('已断开与外部服务的连接（外部服务继续运行，桌面端不会停止它）');
+      serverReady = false;
+      serverAttached = false;
+      hasNavigated = false;
+      updateTray();
+      emitServerStatus('stopped', { external: true });
+    }
+    return;
+  }
+  if (splashState === 'left') {
+    loadSplash().catch((err) => appendLog(`重新加载启动屏失败: ${err && err.message ? err.message : err}`, 'warn'));
+  }
+  sendLog('正在停止服务...');
+  const wasReady = serverReady;
+  serverReady = false;
+  hasNavigated = false;
+  updateTray();
+  try {
+    if (typeof serverModule.gracefulShutdown === 'function') {
+      await shutdownWithTimeout(() => shutdownServerModule(reason, wasReady));
+    }
+  } catch (err) {
+    appendLog(`停止服务异常: ${err && err.message ? err.message : err}`, 'error');
   }
-  return false;
+  releaseServerModule();
+  serverAttached = false;
+  sendLog('服务已停止');
+  emitServerStatus('stopped');
 }
 
-// ── 向渲染层推送启动日志/错误 ─────────────────────────────────────────
-function sendLog(msg) {
-  appendLog(msg);
-  if (mainWindow && !mainWindow.isDestroyed()) {
-    mainWindow.webContents.send('startup-log', msg);
-  }
-}
+// 服务启停/重启串行执行，避免托盘连续点击导致并发 start/stop 互相踩踏。
+// 前一个任务失败不影响后续任务执行。
+let lifecycleChain = Promise.resolve();
 
-function sendError(report) {
-  appendLog(`✗ 启动失败:\n${report}`);
-  if (watchdogTimer) { clearTimeout(watchdogTimer); watchdogTimer = null; }
-  if (mainWindow && !mainWindow.isDestroyed()) {
-    mainWindow.webContents.send('startup-error', report);
-  }
+function runLifecycle(name, task) {
+  const run = lifecycleChain.then(task);
+  lifecycleChain = run.catch((err) => {
+    appendLog(`${name} 失败: ${err && err.message ? err.message : err}`);
+  });
+  return run;
 }
 
-// ── 在主进程内启动后端服务 ─────────────────────────────────────────────
-async function startInProcessServer() {
-  // 开发态：通过环境变量 AIC_ELECTRON_DEV_SERVER 指向 vite dev server，UI 走热更新；
-  // 生产态：UI 由 Express 的 dist/ui 静态资源提供。
-  const isDev = !!process.env.AIC_ELECTRON_DEV_SERVER;
+// ── 创建主窗口 ────────────────────────────────────────────────────────
+const DEFAULT_WINDOW_TITLE = 'AI Code Switch';
+const DEFAULT_WINDOW_WIDTH = 1200;
+const DEFAULT_WINDOW_HEIGHT = 720;
+const MIN_WINDOW_WIDTH = 800;
+const MIN_WINDOW_HEIGHT = 600;
+const MAX_WINDOW_SIZE = 10000;
 
-  // 服务端入口解析：基于 electron/main.js 自身的 __dirname 推导仓库/应用根。
-  // 不使用 app.getAppPath()：当以 `electron electron/main.js` 显式入口启动时，
-  // getAppPath() 可能返回该文件路径而非目录；__dirname 在「显式文件入口」与
-  // 「打包后（approot/electron/main.js + approot/dist）」两种布局下都稳定。
-  const repoRoot = path.resolve(__dirname, '..');
-  const serverEntry = path.join(repoRoot, 'dist', 'server', 'main.js');
-  appendLog(`Electron 主进程模式: ${isDev ? '开发' : '生产'}`);
-  appendLog(`应用根目录: ${repoRoot}`);
-  appendLog(`服务入口: ${serverEntry}`);
+// WINDOW_WIDTH / WINDOW_HEIGHT：非整数或超出 [最小尺寸, 10000] 的值视为无效，回退默认并记录
+function readWindowSize(key, min, fallback) {
+  const raw = (config[key] || '').trim();
+  if (!raw) return null;
+  const n = Number(raw);
+  if (Number.isInteger(n) && n >= min && n <= MAX_WINDOW_SIZE) return n;
+  appendLog(`⚠ ${key}=${raw} 无效（需为 ${min}-${MAX_WINDOW_SIZE} 的整数），使用默认值 ${fallback}`, 'warn');
+  return null;
+}
+
+// 窗口位置与尺寸记录在 ~/.aicodeswitch/window.json（关窗时写入），下次启动按上次的几何恢复，优先于
+// WINDOW_WIDTH / WINDOW_HEIGHT 与默认最大化。显示器布局变化后若原位置已不可见（与任何显示器工作区的
+// 重叠不足 100×50），则丢弃位置只保留尺寸；尺寸同时限制在目标显示器工作区内，避免出现看不见的窗口。
+const WINDOW_STATE_FILE = path.join(LOG_DIR, 'window.json');
+const MIN_VISIBLE_WIDTH = 100;
+const MIN_VISIBLE_HEIGHT = 50;
 
-  if (!fs.existsSync(serverEntry)) {
-    throw new Error(`Server entry file not found: ${serverEntry}\n请先执行 \`npm run build\`（或 yarn build）生成 dist/server。`);
+function loadWindowState() {
+  let saved;
+  try {
+    saved = JSON.parse(fs.readFileSync(WINDOW_STATE_FILE, 'utf-8'));
+  } catch {
+    return null;
+  }
+  const { x, y, width, height, maximized } = saved || {};
+  if (![width, height].every(Number.isInteger)) return null;
+  const bounds = { width: Math.max(width, MIN_WINDOW_WIDTH), height: Math.max(height, MIN_WINDOW_HEIGHT) };
+  let area = screen.getPrimaryDisplay().workArea;
+  if (Number.isInteger(x) && Number.isInteger(y)) {
+    const visible = screen.getAllDisplays().find(({ workArea: a }) => (
+      Math.min(x + bounds.width, a.x + a.width) - Math.max(x, a.x) >= MIN_VISIBLE_WIDTH &&
+      Math.min(y + bounds.height, a.y + a.height) - Math.max(y, a.y) >= MIN_VISIBLE_HEIGHT
+    ));
+    if (visible) {
+      area = visible.workArea;
+      bounds.x = x;
+      bounds.y = y;
+    } else {
+      appendLog('上次的窗口位置已不在任何显示器范围内，改为居中显示');
+    }
   }
+  bounds.width = Math.min(bounds.width, area.width);
+  bounds.height = Math.min(bounds.height, area.height);
+  return { bounds, maximized: maximized === true };
+}
 
-  // 内嵌进程模式：服务端 shutdown 后不 process.exit，且被 require 时不自动 start
-  process.env.AIC_IN_PROCESS = '1';
-  process.env.PORT = String(port);
-  process.env.NODE_ENV = 'production';
+function saveWindowState(win) {
+  try {
+    const { x, y, width, height } = win.getNormalBounds();
+    fs.writeFileSync(WINDOW_STATE_FILE, JSON.stringify({ x, y, width, height, maximized: win.isMaximized() }));
+  } catch (err) {
+    appendLog(`保存窗口位置失败: ${err && err.message ? err.message : err}`, 'warn');
+  }
+}
 
-  // 清理 require 缓存，避免开发态热重载时旧实例残留
-  try { delete require.cache[require.resolve(serverEntry)]; } catch { /* ignore */ }
+// SHOW_WINDOW=immediate|on_ready（默认 immediate）：immediate 先显示启动屏；on_ready 保持窗口隐藏，
+// 管理界面加载成功后才显示。启动失败、需要弹出对话框或用户主动唤起窗口时同样会显示，不会一直隐藏
+function getShowWindowMode() {
+  const mode = String(config.SHOW_WINDOW || '').trim().toLowerCase().replace('-', '_');
+  return mode === 'on_ready' ? 'on_ready' : 'immediate';
+}
 
-  // eslint-disable-next-line global-require, import/no-dynamic-require
-  serverModule = require(serverEntry);
-  if (!serverModule || typeof serverModule.start !== 'function') {
-    throw new Error('服务入口未导出 start() 函数，请检查 src/server/main.ts 的导出。');
+// 首次显示窗口（每个窗口只执行一次）：按需最大化，并把应用拉到前台
+function revealMainWindow() {
+  if (!mainWindow || mainWindow.isDestroyed() || windowRevealed) return;
+  windowRevealed = true;
+  if (mainWindow.maximizable && windowMaximize) {
+    mainWindow.maximize();
   }
 
-  // 进程内启动服务（异步）；start 内部的致命错误会 process.exit(1)，由系统兜底
-  serverModule.start().catch((err) => {
-    appendLog(`服务启动异常: ${err && err.stack ? err.stack : err}`);
-    sendError(`服务启动异常：${err && err.message ? err.message : err}`);
-  });
-
-  sendLog('正在等待服务就绪...');
-  const ok = await waitForServer();
-  if (!ok) {
-    throw new Error(`服务在 30 秒内未就绪（端口 ${port}）。详见 ~/.aicodeswitch/app-launch-debug.log`);
+  // macOS：把 app 拉到前台并显示/聚焦窗口，避免从 CLI 启动时只在 dock 出现图标
+  if (process.platform === 'darwin' && app.dock && typeof app.dock.show === 'function') {
+    app.dock.show();
   }
-  serverReady = true;
-  sendLog('服务已就绪');
+  mainWindow.show();
+  mainWindow.focus();
 }
 
-// ── 创建主窗口 ────────────────────────────────────────────────────────
 function createWindow() {
   const isDev = !!process.env.AIC_ELECTRON_DEV_SERVER;
 
   // 窗口图标（Windows/Linux 任务栏 + 开发期可见）；macOS 应用图标来自 .app bundle，
   // 由 electron-builder 在打包时根据 build/icon.png 自动生成 icon.icns。
-  const iconPath = path.join(__dirname, '..', 'build', 'icon.png');
+  const iconPath = resolveIconPath();
+  // 白标定制：WINDOW_TITLE / WINDOW_WIDTH / WINDOW_HEIGHT，未配置时使用默认值
+  const customTitle = (config.WINDOW_TITLE || '').trim();
+  const customWidth = readWindowSize('WINDOW_WIDTH', MIN_WINDOW_WIDTH, DEFAULT_WINDOW_WIDTH);
+  const customHeight = readWindowSize('WINDOW_HEIGHT', MIN_WINDOW_HEIGHT, DEFAULT_WINDOW_HEIGHT);
+  const restored = loadWindowState();
   const windowOptions = {
-    width: 1200,
-    height: 720,
-    minWidth: 800,
-    minHeight: 600,
+    width: customWidth || DEFAULT_WINDOW_WIDTH,
+    height: customHeight || DEFAULT_WINDOW_HEIGHT,
+    ...(restored ? restored.bounds : {}),
+    minWidth: MIN_WINDOW_WIDTH,
+    minHeight: MIN_WINDOW_HEIGHT,
     resizable: true,
-    title: 'AI Code Switch',
+    show: false,
+    title: customTitle || DEFAULT_WINDOW_TITLE,
     backgroundColor: '#081c15',
     webPreferences: {
       preload: path.join(__dirname, 'preload.js'),
@@ -177,117 +1840,900 @@ function createWindow() {
       sandbox: false,
     },
   };
-  if (fs.existsSync(iconPath)) {
+  if (iconPath) {
     windowOptions.icon = iconPath;
   }
   mainWindow = new BrowserWindow(windowOptions);
 
-  // 先加载本地启动屏（显示启动日志/错误面板）
-  mainWindow.loadFile(path.join(__dirname, 'loading.html'));
-
-  // 启动即最大化（保留 width/height 作为不可最大化时的兜底尺寸）
-  if (mainWindow.maximizable) {
-    mainWindow.maximize();
-  }
+  // 先加载本地启动屏（显示启动日志/错误面板）；窗口在启动屏首帧绘制完成后再显示，避免空白窗口
+  loadSplash().catch((err) => appendLog(`加载启动屏失败: ${err && err.message ? err.message : err}`, 'error'));
 
-  // macOS：把 app 拉到前台并显示/聚焦窗口，避免从 CLI 启动时只在 dock 出现图标
-  if (process.platform === 'darwin' && app.dock && typeof app.dock.show === 'function') {
-    app.dock.show();
+  // 配置了 WINDOW_TITLE 时固定窗口标题，不随页面 <title> 变化
+  if (customTitle) {
+    mainWindow.on('page-title-updated', (e) => e.preventDefault());
   }
-  mainWindow.show();
-  mainWindow.focus();
 
-  // 启动看门狗：超时未就绪则展示错误面板，避免无限转圈
-  watchdogTimer = setTimeout(() => {
-    if (!serverReady) {
-      sendError(
-        `启动超时：在 ${WATCHDOG_MS / 1000} 秒内未收到服务就绪信号。\n` +
-        '可能是后端启动卡死或端口冲突，详见 ~/.aicodeswitch/app-launch-debug.log。',
-      );
+  // 启动即最大化（保留 width/height 作为不可最大化时的兜底尺寸）；显式配置了窗口尺寸时按配置尺寸显示；
+  // 有上次记录的窗口几何时按记录恢复（包括是否最大化）
+  windowRevealed = false;
+  windowMaximize = restored ? restored.maximized : (!customWidth && !customHeight);
+  mainWindow.once('ready-to-show', () => {
+    if (getShowWindowMode() === 'on_ready') {
+      appendLog('SHOW_WINDOW=on_ready：窗口将在管理界面加载完成后显示');
+      return;
     }
-  }, WATCHDOG_MS);
+    revealMainWindow();
+  });
 
   // macOS：点关闭按钮只是隐藏窗口（不销毁、不退出），点 dock 图标可再次显示；
   // 真正退出走 before-quit（Cmd+Q / dock Quit）→ gracefulQuit，此时 isQuitting=true 放行关闭。
-  // Windows / Linux：保持「关窗即退出」的常规行为。
+  // Windows / Linux：默认保持「关窗即退出」；配置 CLOSE_TO_TRAY=true 时改为隐藏到托盘，服务继续运行。
+  // 配置 KEEP_ALIVE_ON_CLOSE=true 时（所有平台）关窗直接销毁窗口释放界面资源，应用与服务继续运行，
+  // 之后从托盘 / dock / 再次启动应用（单实例唤起）重新打开窗口时会直接导航到管理界面。
   mainWindow.on('close', (e) => {
-    if (process.platform === 'darwin' && !isQuitting) {
+    saveWindowState(mainWindow);
+    if (isQuitting || configFlag(config, 'KEEP_ALIVE_ON_CLOSE')) return;
+    if (process.platform === 'darwin' || (tray && configFlag(config, 'CLOSE_TO_TRAY'))) {
       e.preventDefault();
       mainWindow.hide();
     }
   });
 
-  // 窗口真正销毁时（仅退出流程）清理引用
-  mainWindow.on('closed', () => { mainWindow = null; });
+  // 窗口真正销毁时（退出流程 / KEEP_ALIVE_ON_CLOSE 关窗）清理引用
+  mainWindow.on('closed', () => {
+    mainWindow = null;
+    hasNavigated = false;
+  });
+
+  // 新窗口（target=_blank、window.open）一律不在应用内打开，http(s) 链接交给系统浏览器
+  mainWindow.webContents.setWindowOpenHandler(({ url }) => {
+    if (/^https?:/i.test(url)) shell.openExternal(url);
+    return { action: 'deny' };
+  });
+
+  // 主窗口只停留在启动屏与管理界面的源内：页面内跳转到其它地址时取消，http(s) 链接改用系统浏览器打开
+  mainWindow.webContents.on('will-navigate', (e, url) => {
+    if (isTrustedUrl(url)) return;
+    e.preventDefault();
+    appendLog(`已阻止主窗口导航到 ${url}`);
+    if (/^https?:/i.test(url)) shell.openExternal(url);
+  });
 
   // 捕获窗口内的导航错误（服务已就绪但打不开界面）
-  mainWindow.webContents.on('did-fail-load', (_e, errorCode, errorDescription) => {
-    if (!serverReady) return; // 启动屏阶段忽略
-    sendError(`界面加载失败 (code=${errorCode}): ${errorDescription}`);
+  mainWindow.webContents.on('did-fail-load', (_e, errorCode, errorDescription, _url, isMainFrame) => {
+    if (!hasNavigated) return; // 启动屏阶段与首次导航重试期间由 navigateToServer 处理
+    // 子框架加载失败、导航被取消（ERR_ABORTED）不影响已加载的管理界面，不当作错误
+    if (!isMainFrame || errorCode === -3) return;
+    sendError(new StartupError('NAVIGATION_FAILED', `界面加载失败 (code=${errorCode}): ${errorDescription}`));
+  });
+
+  // Ctrl/Cmd+Shift+R：重新加载管理界面（不重启服务），页面卡在异常状态时的快速恢复手段
+  mainWindow.webContents.on('before-input-event', (e, input) => {
+    if (input.type === 'keyDown' && input.shift && (input.control || input.meta) && input.key.toLowerCase() === 'r') {
+      e.preventDefault();
+      reloadWindow().catch((err) => appendLog(err && err.message ? err.message : String(err)));
+    }
   });
 
   // 渲染进程崩溃/被杀时记录，便于诊断「窗口出现后又消失」类问题
   mainWindow.webContents.on('render-process-gone', (_e, details) => {
-    appendLog(`⚠ 渲染进程异常退出: reason=${details && details.reason}`);
+    appendLog(`⚠ 渲染进程异常退出: reason=${details && details.reason}`, 'warn');
   });
 
   return mainWindow;
 }
 
+// ── 导航到管理界面（带重试） ─────────────────────────────────────────
+// loadURL 只在网络层失败时 reject；服务返回 500 等错误页时同样 resolve，
+// 这里通过 did-navigate 拿到 HTTP 状态码，>= 400 视为加载失败并重试。
+async function loadServerPage(targetUrl) {
+  let responseCode = 0;
+  const onNavigate = (_e, _url, httpResponseCode) => { responseCode = httpResponseCode; };
+  mainWindow.webContents.on('did-navigate', onNavigate);
+  try {
+    await mainWindow.loadURL(targetUrl);
+  } finally {
+    mainWindow.webContents.removeListener('did-navigate', onNavigate);
+  }
+  if (responseCode >= 400) {
+    throw new Error(`管理界面返回 HTTP ${responseCode}`);
+  }
+}
+
+async function navigateToServer(targetUrl) {
+  let lastError = null;
+  for (let attempt = 1; attempt <= NAVIGATE_ATTEMPTS; attempt++) {
+    try {
+      splashState = 'left';
+      // eslint-disable-next-line no-await-in-loop
+      await loadServerPage(targetUrl);
+      hasNavigated = true;
+      return;
+    } catch (err) {
+      lastError = err;
+      if (attempt < NAVIGATE_ATTEMPTS) {
+        // eslint-disable-next-line no-await-in-loop
+        await new Promise((r) => setTimeout(r, NAVIGATE_RETRY_DELAY_MS));
+      }
+    }
+  }
+  appendLog(`导航到 ${targetUrl} 失败（已重试 ${NAVIGATE_ATTEMPTS} 次）: ${lastError && lastError.message ? lastError.message : lastError}`, 'error');
+  throw lastError;
+}
+
+// ── 托盘 ──────────────────────────────────────────────────────────────
+// electron/icon.png 由 electron:icon 生成并随 electron/** 打包；build/icon.png 仅开发期存在
+function resolveIconPath() {
+  const candidates = [
+    path.join(__dirname, 'icon.png'),
+    path.join(__dirname, '..', 'build', 'icon.png'),
+  ];
+  return candidates.find((p) => fs.existsSync(p)) || null;
+}
+
+function updateTray() {
+  if (!tray) return;
+  const running = serverReady;
+  tray.setToolTip(running ? `AI Code Switch - 服务运行中（端口 ${port}）` : 'AI Code Switch - 服务已停止');
+  tray.setContextMenu(Menu.buildFromTemplate([
+    { label: '显示主窗口', click: () => showMainWindow() },
+    { type: 'separator' },
+    { label: '启动服务', enabled: !running, click: () => runLifecycle('启动服务', () => startAndNavigate()) },
+    { label: '停止服务', enabled: running, click: () => runLifecycle('停止服务', () => stopInProcessServer('TRAY_STOP')) },
+    {
+      label: '重启服务',
+      click: () => runLifecycle('重启服务', async () => {
+        emitServerStatus('restarting');
+        await stopInProcessServer('TRAY_RESTART');
+        await startAndNavigate();
+      }),
+    },
+    {
+      label: '重新加载界面',
+      enabled: running,
+      accelerator: 'CommandOrControl+Shift+R',
+      click: () => reloadWindow().catch((err) => appendLog(err && err.message ? err.message : String(err))),
+    },
+    { type: 'separator' },
+    {
+      label: '编辑配置文件',
+      click: () => openConfigFile().catch((err) => appendLog(err && err.message ? err.message : String(err))),
+    },
+    { type: 'separator' },
+    { label: '退出', click: () => app.quit() },
+  ]));
+}
+
+function createTray() {
+  const iconPath = resolveIconPath();
+  const icon = iconPath
+    ? nativeImage.createFromPath(iconPath).resize({ width: 16, height: 16 })
+    : nativeImage.createEmpty();
+  tray = new Tray(icon);
+  tray.on('click', () => showMainWindow());
+  updateTray();
+}
+
 // ── 启动主流程 ────────────────────────────────────────────────────────
-async function bootstrap() {
-  port = readPortFromConfig();
-  appendLog('=== AICodeSwitch Electron 启动日志 ===');
+// ── 启动耗时统计 ──────────────────────────────────────────────────────
+// 每次启动记录各阶段距开始的耗时，导航完成（或无窗口时服务就绪）后输出一行汇总，例如
+// `startup: locate=3ms load=412ms ready=2310ms nav=2480ms`，便于分析「启动慢」反馈
+let startupTiming = null;
 
-  createWindow();
-  sendLog('应用已启动');
+function markStartup(phase) {
+  if (!startupTiming) return;
+  startupTiming.marks.push([phase, Date.now() - startupTiming.startedAt]);
+  logPhase = phase;
+}
 
-  try {
-    await startInProcessServer();
-  } catch (err) {
-    sendError(err && err.message ? err.message : String(err));
+function logStartupTiming() {
+  if (!startupTiming) return;
+  appendLog(`startup: ${startupTiming.marks.map(([phase, ms]) => `${phase}=${ms}ms`).join(' ')}`);
+  startupTiming = null;
+  logPhase = null;
+}
+
+// 启动看门狗：超时未就绪则展示错误面板，避免无限转圈。只在真正的启动流程中设置（重新打开窗口、
+// 服务已停止时不会有「就绪信号」，不能据此报启动超时）
+function armStartupWatchdog() {
+  if (watchdogTimer) clearTimeout(watchdogTimer);
+  const watchdogMs = Math.max(WATCHDOG_MS, getHealthWaitSeconds() * 1000 + WATCHDOG_GRACE_MS);
+  watchdogTimer = setTimeout(() => {
+    watchdogTimer = null;
+    if (!serverReady) {
+      sendError(new StartupError(
+        'WATCHDOG_TIMEOUT',
+        `启动超时：在 ${Math.round(watchdogMs / 1000)} 秒内未收到服务就绪信号。\n` +
+        '可能是后端启动卡死或端口冲突，详见 ~/.aicodeswitch/app-launch-debug.log。',
+      ));
+    }
+  }, watchdogMs);
+}
+
+// 启动进程内服务并导航到管理界面；首次启动与托盘「启动/重启服务」共用
+// STARTUP_RETRIES：整个启动流程（加载 → 启动 → 等待就绪）失败后自动重试的次数（默认 0，最多 5），
+// 每次间隔 3 秒，用尽后才展示错误；用于平滑安装后首次启动时杀毒扫描等偶发问题。
+// 配置类错误（入口缺失、端口被占用、地址被拒绝等）重试无意义，直接报错。
+const MAX_STARTUP_RETRIES = 5;
+const STARTUP_RETRY_DELAY_MS = 3000;
+const NON_RETRYABLE_STARTUP_ERRORS = new Set([
+  'APP_DIR_UNAVAILABLE', 'ENTRY_NOT_FOUND', 'ENTRY_INVALID', 'ENTRY_ACCESS_DENIED', 'WORKING_DIR_INVALID',
+  'PORT_IN_USE_SELF', 'PORT_IN_USE_FOREIGN', 'PORT_AUTH_REJECTED', 'REMOTE_HOST_REFUSED',
+]);
+
+function getStartupRetries() {
+  const n = parseInt(config.STARTUP_RETRIES, 10);
+  return Number.isFinite(n) && n > 0 ? Math.min(n, MAX_STARTUP_RETRIES) : 0;
+}
+
+async function startAndNavigate() {
+  startupTiming = { startedAt: Date.now(), marks: [] };
+  armStartupWatchdog();
+  logPhase = 'start';
+  if (splashState === 'left') await loadSplash();
+
+  const totalAttempts = getStartupRetries() + 1;
+  for (let attempt = 1; ; attempt++) {
+    if (totalAttempts > 1) appendLog(`── 启动流程第 ${attempt}/${totalAttempts} 次 ──`);
+    try {
+      // eslint-disable-next-line no-await-in-loop
+      await (isExternalServer() ? attachExternalServer() : startInProcessServer());
+      break;
+    } catch (err) {
+      if (err instanceof StartupError && err.code === 'CANCELLED') {
+        appendLog(err.message);
+        startupTiming = null;
+        logPhase = null;
+        return;
+      }
+      const code = err instanceof StartupError ? err.code : 'UNKNOWN';
+      if (attempt >= totalAttempts || isQuitting || NON_RETRYABLE_STARTUP_ERRORS.has(code)) {
+        markStartup('failed');
+        logStartupTiming();
+        sendError(err);
+        return;
+      }
+      appendLog(`启动流程第 ${attempt}/${totalAttempts} 次失败 [${code}]: ${String(err && err.message ? err.message : err).split('\n')[0]}`, 'warn');
+      sendLog(`启动失败，${STARTUP_RETRY_DELAY_MS / 1000} 秒后自动重试（STARTUP_RETRIES）...`);
+      // 每次尝试各自有就绪超时，重试期间不再让整体看门狗提前报错
+      if (watchdogTimer) { clearTimeout(watchdogTimer); watchdogTimer = null; }
+      // eslint-disable-next-line no-await-in-loop
+      await stopInProcessServer('STARTUP_RETRY');
+      // eslint-disable-next-line no-await-in-loop
+      await new Promise((r) => setTimeout(r, STARTUP_RETRY_DELAY_MS));
+    }
+  }
+  updateTray();
+  startHealthMonitor();
+
+  // 服务就绪 → 导航到管理界面（KEEP_ALIVE_ON_CLOSE 下窗口可能已关闭，待重新打开时再导航）
+  if (!mainWindow || mainWindow.isDestroyed()) {
+    if (watchdogTimer) { clearTimeout(watchdogTimer); watchdogTimer = null; }
+    logStartupTiming();
+    return;
+  }
+  await navigateMainWindow();
+  markStartup('nav');
+  logStartupTiming();
+}
+
+// AUTO_START=false：不启动内嵌服务，端口上已有服务可达时直接连接并导航；
+// 否则只给出提示（不是错误），之后可从托盘「启动服务」手动启动
+async function connectWithoutAutoStart() {
+  bindHost = resolveBindHost(config);
+  activeConnectHost = null;
+  if (watchdogTimer) { clearTimeout(watchdogTimer); watchdogTimer = null; }
+  const res = await requestHealth(port);
+  if (isHealthy(res) && !isServerResponse(res)) {
+    sendError(foreignServerError(res));
+    return;
+  }
+  if (!isServerResponse(res)) {
+    sendLog('AUTO_START=false：未自动启动服务，可通过托盘菜单「启动服务」启动');
+    showMessageBox({
+      type: 'info',
+      title: 'AI Code Switch',
+      message: '未自动启动服务',
+      detail: `已配置 AUTO_START=false，且端口 ${port} 上没有正在运行的服务。\n` +
+        '可通过托盘菜单「启动服务」启动，或手动启动后端后重新打开应用。',
+    }).catch(() => { /* ignore */ });
     return;
   }
+  serverReady = true;
+  serverAttached = true;
+  sendLog(`AUTO_START=false：已连接端口 ${port} 上正在运行的服务`);
+  emitServerStatus('ready', { external: true });
+  updateTray();
+  if (!mainWindow || mainWindow.isDestroyed()) return;
+  await navigateMainWindow();
+}
 
-  // 服务就绪 → 导航到管理界面
+// BASE_PATH：管理界面不在根路径时（如反向代理布局下的 /app）追加到导航地址，健康检查不受影响
+function getTargetUrl() {
   const isDev = !!process.env.AIC_ELECTRON_DEV_SERVER;
-  const targetUrl = isDev
-    ? process.env.AIC_ELECTRON_DEV_SERVER
-    : `http://127.0.0.1:${port}`;
+  if (isDev) return process.env.AIC_ELECTRON_DEV_SERVER;
+  return navigationUrl(serverEndpoint());
+}
 
+// AUTH_AUTOLOGIN=true：本机用户已在配置文件里写了 AUTH，导航前用它换取登录 token，由 preload 在页面脚本
+// 运行前写入管理界面的 localStorage（auth_token），免去再次输入鉴权码。只对回环地址上的管理界面生效，
+// ALLOW_REMOTE 放行的远端地址也不会下发 token。
+let autoLogin = null;        // { origin, token }
+
+async function prepareAutoLogin(targetUrl) {
+  autoLogin = null;
+  if (!configFlag(config, 'AUTH_AUTOLOGIN')) return;
+  const { hostname, origin } = new URL(targetUrl);
+  if (!isLoopbackHost(hostname)) {
+    appendLog(`⚠ AUTH_AUTOLOGIN 仅对本机地址生效，已跳过: ${origin}`, 'warn');
+    return;
+  }
+  const token = await requestAuthToken();
+  if (token) {
+    autoLogin = { origin, token };
+    appendLog('AUTH_AUTOLOGIN：已获取登录凭证，将自动登录管理界面');
+  }
+}
+
+async function navigateMainWindow() {
+  const targetUrl = getTargetUrl();
+  await splashLoading;
+  if (!mainWindow || mainWindow.isDestroyed()) return;
+  try {
+    ensureLoopbackHost(new URL(targetUrl).hostname, '管理界面');
+  } catch (err) {
+    sendError(err);
+    return;
+  }
+  await prepareAutoLogin(targetUrl);
   try {
     sendLog(`正在加载 ${targetUrl} ...`);
-    await mainWindow.loadURL(targetUrl);
+    await navigateToServer(targetUrl);
     if (watchdogTimer) { clearTimeout(watchdogTimer); watchdogTimer = null; }
+    revealMainWindow();
+    replayRuntimeWarnings();
+  } catch (err) {
+    sendError(new StartupError('NAVIGATION_FAILED', `服务已就绪，但无法打开管理界面：${err && err.message ? err.message : err}`));
+    // 不等待用户响应，避免弹窗阻塞 runLifecycle 串行队列
+    offerExternalBrowser(targetUrl).catch((e) => appendLog(`浏览器兜底弹窗异常: ${e && e.message ? e.message : e}`, 'warn'));
+  }
+}
+
+// 重新导航到当前生效的服务地址，只刷新界面、不重启服务；窗口已销毁（KEEP_ALIVE_ON_CLOSE）时重新打开。
+// 与托盘启停共用 runLifecycle，避免与重启服务交错
+function reloadWindow() {
+  return runLifecycle('重新加载界面', async () => {
+    if (!serverReady) throw new Error('服务未运行，无法重新加载管理界面');
+    if (!mainWindow || mainWindow.isDestroyed()) {
+      showMainWindow();
+      return getTargetUrl();
+    }
+    appendLog('重新加载管理界面');
+    hasNavigated = false;
+    await navigateMainWindow();
+    return getTargetUrl();
+  });
+}
+
+// 窗口内导航重试用尽（部分 Linux WebKitGTK 环境）但服务正常时，提供用系统浏览器打开的出路
+async function offerExternalBrowser(targetUrl) {
+  if (!mainWindow || mainWindow.isDestroyed()) return;
+  const { response } = await showMessageBox({
+    type: 'warning',
+    title: 'AI Code Switch',
+    message: '无法在应用窗口中打开管理界面',
+    detail: `服务已在运行，可以改用系统默认浏览器访问：\n${targetUrl}`,
+    buttons: ['在浏览器中打开', '取消'],
+    defaultId: 0,
+    cancelId: 1,
+  });
+  if (response !== 0) return;
+  appendLog(`使用系统浏览器打开管理界面: ${targetUrl}`);
+  try {
+    await shell.openExternal(targetUrl);
+  } catch (err) {
+    appendLog(`打开系统浏览器失败: ${err && err.message ? err.message : err}`, 'error');
+  }
+}
+
+// ── 运行时架构检查 ────────────────────────────────────────────────────
+// 内嵌服务跑在 Electron 自带的 Node 上，不存在「系统 Node 与应用架构不一致」；
+// 对应的问题是在 ARM64 设备上装了 x64 安装包（macOS Rosetta / Windows on ARM 仿真），
+// 性能差且原生依赖可能异常。仅提示，不阻断启动。
+function checkRuntimeArch() {
+  if (!app.runningUnderARM64Translation) return null;
+  const warning = `当前为 ${process.arch} 版本，正在 ARM64 设备上经转译运行，性能较差且可能出现兼容问题，建议改装 arm64 版本安装包`;
+  emitRuntimeWarning('arch', warning, process.arch, 'arm64');
+  return warning;
+}
+
+// ── 渲染层可调用的 IPC 命令（经 preload 暴露为 window.aicodeswitch.*） ─────
+// 重新读取 aicodeswitch.conf：端口 / AUTH / NODE_ENV / ENV_* 变化时重启内嵌服务并重新导航，其余配置即时生效。
+// 与托盘启停共用 runLifecycle 串行执行，避免并发重启。
+function needsServerRestart(prev, next) {
+  if (readPortFromConfig(prev) !== readPortFromConfig(next)) return true;
+  if (SERVER_CONFIG_KEYS.some((key) => (prev[key] || '') !== (next[key] || ''))) return true;
+  if ((prev.NODE_ENV || '') !== (next.NODE_ENV || '')) return true;
+  return JSON.stringify(getExtraEnv(prev)) !== JSON.stringify(getExtraEnv(next));
+}
+
+// 同步配置到 process.env：服务端的 dotenv 不覆盖已存在的变量，重启前需手动刷新。
+// 只同步服务端读取的配置项与 ENV_* 转发的变量；从配置中删除的 ENV_FOO 需撤销的是转发出去的 FOO
+function setServerEnv(key, value) {
+  const resolved = value !== undefined && value !== '' ? value : LAUNCH_ENV[key];
+  if (resolved === undefined) delete process.env[key];
+  else process.env[key] = resolved;
+}
+
+function syncConfigEnv(prev, next) {
+  for (const key of SERVER_CONFIG_KEYS) setServerEnv(key, next[key]);
+  const nextExtra = getExtraEnv(next);
+  for (const key of Object.keys(getExtraEnv(prev))) {
+    if (!(key in nextExtra)) setServerEnv(key, undefined);
+  }
+  Object.assign(process.env, nextExtra);
+}
+
+function reloadConfig() {
+  return runLifecycle('重新加载配置', async () => {
+    const prev = config;
+    config = readConfig();
+    appendLog('已重新加载配置文件');
+    reportConfigWarnings();
+    const restarted = needsServerRestart(prev, config);
+    if (restarted) {
+      syncConfigEnv(prev, config);
+      emitServerStatus('restarting');
+      await stopInProcessServer('CONFIG_RELOAD');
+      port = readPortFromConfig(config);
+      await startAndNavigate();
+    }
+    updateTray();
+    return {
+      restarted,
+      port,
+      configuredPort: readPortFromConfig(config),
+      config: redactConfig(config),
+    };
+  });
+}
+
+// 热重启：重新读取配置后只重启内嵌服务（新的 ENV_* / NODE_ENV 随之生效；NODE_ARGS 只在应用启动时应用），不重建窗口，
+// 就绪后重新导航当前窗口。stopInProcessServer 会取消尚未执行的自动恢复；已排队的自动恢复任务
+// 会先检查服务是否已健康，因此不会与这里的重启重复拉起服务
+function restartServer() {
+  return runLifecycle('重启服务', async () => {
+    const prev = config;
+    config = readConfig();
+    appendLog('重启服务：已重新加载配置文件');
+    reportConfigWarnings();
+    syncConfigEnv(prev, config);
+    emitServerStatus('restarting');
+    await stopInProcessServer('IPC_RESTART');
+    port = readPortFromConfig(config);
+    await startAndNavigate();
+    if (serverReady) restartAttempts = 0;
+    updateTray();
+    return { running: serverReady, port, url: serverReady ? getTargetUrl() : null };
+  });
+}
+
+// 用系统默认编辑器打开配置文件（不存在时先写入带注释的模板）
+// 使用配置档时打开配置档文件，否则打开（必要时创建）默认配置文件
+async function openConfigFile() {
+  const file = activeProfile ? activeProfile.path : ensureConfigFile();
+  const error = await shell.openPath(file);
+  if (error) throw new Error(`无法打开配置文件 ${file}: ${error}`);
+  return file;
+}
+
+// 重置桌面端运行数据（排障用）：删除启动日志、state.json、logs/ 下的服务日志与诊断报告，keepConfig=false 时连同配置文件。
+// 服务数据（fs-db / data / backup）绝不删除。先停止内嵌服务，与托盘启停共用 runLifecycle 串行执行避免竞争；
+// 服务保持停止状态，之后由用户从托盘「启动服务」或重新打开应用。
+function resetAppData(keepConfig = true) {
+  return runLifecycle('重置应用数据', async () => {
+    if (serverModule) await stopInProcessServer('RESET_APP_DATA');
+    const removed = [];
+    const remove = (file) => {
+      if (!fs.existsSync(file)) return;
+      if (file === SERVER_LOG_FILE) {
+        // 输出捕获仍以追加方式持有该文件，清空而不是删除（Windows 下无法删除已打开的文件）
+        fs.truncateSync(file, 0);
+      } else {
+        fs.rmSync(file, { recursive: true, force: true });
+      }
+      removed.push(file);
+    };
+    remove(LOG_FILE);
+    remove(STATE_FILE);
+    remove(WINDOW_STATE_FILE);
+    const logsDir = path.dirname(SERVER_LOG_FILE);
+    if (fs.existsSync(logsDir)) {
+      for (const name of fs.readdirSync(logsDir)) remove(path.join(logsDir, name));
+    }
+    if (!keepConfig) remove(CONFIG_PATH);
+    recentLogLines.length = 0;
+    appendLog(`已重置应用数据（${keepConfig ? '保留' : '删除'}配置文件）: ${removed.join(', ') || '无文件'}`);
+    return { removed };
+  });
+}
+
+// 汇总诊断信息供排障页展示 / 复制进问题反馈。各项检查相互独立：单项失败只记录在该项的 error 中。
+async function runDiagnosticCheck(task) {
+  try {
+    return { ok: true, ...(await task()) };
+  } catch (err) {
+    return { ok: false, error: err && err.message ? err.message : String(err) };
+  }
+}
+
+async function diagnose() {
+  const configuredPort = readPortFromConfig(config);
+  return {
+    at: new Date().toISOString(),
+    runtime: await runDiagnosticCheck(() => ({
+      appVersion: app.getVersion(),
+      electron: process.versions.electron,
+      node: process.versions.node,
+      execPath: process.execPath,
+      platform: `${process.platform} ${os.release()}`,
+      arch: process.arch,
+      // 内嵌服务与 Electron 共用运行时，架构问题只会来自 ARM64 设备上运行 x64 安装包
+      archMatch: !app.runningUnderARM64Translation,
+    })),
+    serverEntry: await runDiagnosticCheck(() => ({ path: locateServerEntry() })),
+    config: await runDiagnosticCheck(() => ({
+      path: CONFIG_PATH,
+      exists: fs.existsSync(CONFIG_PATH),
+      profile: activeProfile ? activeProfile.name : null,
+    })),
+    dataDir: await runDiagnosticCheck(() => {
+      fs.accessSync(LOG_DIR, fs.constants.W_OK);
+      return { path: LOG_DIR, writable: true };
+    }),
+    server: await runDiagnosticCheck(() => ({
+      state: serverReady ? 'ready' : (serverModule ? 'starting' : 'stopped'),
+      external: serverAttached || isExternalServer(),
+      configuredPort,
+      port,
+      bindHost,
+    })),
+    configuredPortOwner: await runDiagnosticCheck(async () => ({
+      port: configuredPort,
+      // free / self（AI Code Switch 服务，可能就是本应用）/ protected / foreign
+      owner: await identifyPortOwner(configuredPort),
+    })),
+  };
+}
+
+// 设置页用：配置文件原值与实际生效值对照（默认值、环境变量覆盖、AUTO_PORT 等运行期调整之后）。
+// effective 中每项为 { value, source }，source: env 环境变量 / profile 配置档 / config 配置文件 / default 默认值 /
+// runtime 运行期调整
+function getEffectiveConfig() {
+  const fromConfig = (key, value) => ({ value, source: configSource(key) });
+  const configuredPort = readPortFromConfig(config);
+  let entry = serverEntryPath;
+  if (!entry) {
+    try { entry = locateServerEntry(); } catch { entry = null; }
+  }
+  return {
+    path: CONFIG_PATH,
+    profile: activeProfile ? { name: activeProfile.name, path: activeProfile.path } : null,
+    raw: redactConfig(config),
+    effective: {
+      PORT: serverReady && port !== configuredPort
+        ? { value: port, source: 'runtime' }
+        : fromConfig('PORT', configuredPort),
+      AUTH: fromConfig('AUTH', String(config.AUTH || '').trim() !== ''),
+      EXTERNAL_SERVER: configFlag(process.env, 'EXTERNAL_SERVER')
+        ? { value: true, source: 'env' }
+        : fromConfig('EXTERNAL_SERVER', isExternalServer()),
+      AUTO_START: fromConfig('AUTO_START', !/^(0|false|no|off)$/i.test(String(config.AUTO_START || '').trim())),
+      AUTO_PORT: fromConfig('AUTO_PORT', configFlag(config, 'AUTO_PORT')),
+      NODE_ENV: fromConfig('NODE_ENV', (config.NODE_ENV || '').trim() || 'production'),
+      LOG_LEVEL: fromConfig('LOG_LEVEL', currentLogLevel()),
+      LOG_TO_STDOUT: fromConfig('LOG_TO_STDOUT', shouldEchoOutput()),
+      LOG_FORMAT: fromConfig('LOG_FORMAT', isJsonLogFormat() ? 'json' : 'text'),
+      HEADLESS: fromConfig('HEADLESS', isHeadless()),
+      SHOW_WINDOW: fromConfig('SHOW_WINDOW', getShowWindowMode()),
+      BASE_PATH: fromConfig('BASE_PATH', normalizeUrlPath(config.BASE_PATH) || '/'),
+      HEALTH_PATH: fromConfig('HEALTH_PATH', getHealthPaths().join(',')),
+      HEALTH_MAX_ATTEMPTS: fromConfig('HEALTH_MAX_ATTEMPTS', getHealthMaxAttempts()),
+      SHUTDOWN_TIMEOUT: fromConfig('SHUTDOWN_TIMEOUT', getShutdownTimeoutMs() / 1000),
+      SERVER_ENTRY: fromConfig('SERVER_ENTRY', entry),
+      WORKING_DIR: fromConfig('WORKING_DIR', process.cwd()),
+    },
+    runtime: {
+      running: serverReady,
+      bindHost,
+      connectHost: connectHost(),
+      url: getTargetUrl(),
+    },
+  };
+}
+
+// 设置页选择端口前的可用性检查：在本机回环地址上试绑定（立即释放，不影响运行中的服务），
+// 被占用时用健康标记区分占用方。返回 { port, available, owner }，owner 同 identifyPortOwner，
+// 另有 'current' 表示正是本应用当前运行的服务
+async function checkPortAvailable(targetPort) {
+  const n = Number(targetPort);
+  if (!Number.isInteger(n) || n < 1 || n > 65535) throw new Error(`无效端口: ${targetPort}`);
+  if (serverReady && n === port) return { port: n, available: false, owner: 'current' };
+  if (await isPortFree(n, '127.0.0.1')) return { port: n, available: true, owner: 'free' };
+  const res = await requestHealth(n);
+  let owner = 'foreign';
+  if (isOwnHealthResponse(res)) owner = 'self';
+  else if (isAuthRejected(res)) owner = 'protected';
+  return { port: n, available: false, owner };
+}
+
+function getLogs(lines) {
+  const count = Math.min(Math.max(parseInt(lines, 10) || 200, 1), 5000);
+  return tailFile(SERVER_LOG_FILE, count);
+}
+
+// ── 问题反馈包 ────────────────────────────────────────────────────────
+// 一次导出维护者需要的全部信息：系统 / 运行时信息、打码后的配置与生效配置、诊断结果、
+// 最近一次启动失败报告与最近的日志，打包为 zip（见 zip.js）写到用户选择的位置。HEADLESS 下直接写入
+// ~/.aicodeswitch/support：不放在 logs/ 下，否则 resetAppData 清理日志时会连同刚导出的反馈包一起删掉。
+// AUTH 与 ENV_* 等敏感值经 redactConfig 打码后才写入。
+const SUPPORT_BUNDLE_LOG_LINES = 2000;
+const SUPPORT_BUNDLE_DIR = path.join(LOG_DIR, 'support');
+
+async function collectSupportBundle() {
+  const text = (value) => Buffer.from(typeof value === 'string' ? value : `${JSON.stringify(value, null, 2)}\n`, 'utf-8');
+  const tail = (file) => text(`${tailFile(file, SUPPORT_BUNDLE_LOG_LINES).join('\n')}\n`);
+  const system = [
+    `导出时间: ${new Date().toISOString()}`,
+    `应用版本: ${app.getVersion()}${app.isPackaged ? '' : '（开发版）'}`,
+    `Electron: ${process.versions.electron} / Chrome: ${process.versions.chrome}`,
+    // 内嵌服务运行在 Electron 自带的 Node 上，Node 路径即可执行文件
+    `Node: ${process.versions.node} (${process.execPath})`,
+    `系统: ${process.platform} ${os.release()} (${process.arch}${app.runningUnderARM64Translation ? '，经 ARM64 转译运行' : ''})`,
+    `内存: ${Math.round(os.totalmem() / 1024 / 1024)} MB / CPU: ${os.cpus().length} 核`,
+    `工作目录: ${process.cwd()}`,
+    `数据目录: ${LOG_DIR}`,
+  ];
+  const entries = [
+    { name: 'system.txt', data: text(`${system.join('\n')}\n`) },
+    { name: 'config.txt', data: text(`${Object.entries(redactConfig(config)).map(([k, v]) => `${k}=${v}`).join('\n')}\n`) },
+    { name: 'effective-config.json', data: text(getEffectiveConfig()) },
+    { name: 'diagnose.json', data: text(await diagnose()) },
+  ];
+  for (const [name, file] of [
+    ['startup-error.txt', STARTUP_REPORT_FILE],
+    ['logs/app-launch-debug.log', LOG_FILE],
+    ['logs/server.log', SERVER_LOG_FILE],
+  ]) {
+    if (!fs.existsSync(file)) continue;
+    entries.push({ name, data: name.startsWith('logs/') ? tail(file) : fs.readFileSync(file) });
+  }
+  return entries;
+}
+
+// 返回写入的 zip 路径；用户取消保存对话框时返回 null
+async function exportSupportBundle() {
+  const stamp = new Date().toISOString().replace(/[:.]/g, '-');
+  const fileName = `aicodeswitch-support-${stamp}.zip`;
+  let target = path.join(SUPPORT_BUNDLE_DIR, fileName);
+  if (isHeadless()) {
+    fs.mkdirSync(SUPPORT_BUNDLE_DIR, { recursive: true });
+  } else {
+    const options = {
+      title: '导出问题反馈包',
+      defaultPath: path.join(app.getPath('desktop'), fileName),
+      filters: [{ name: 'Zip', extensions: ['zip'] }],
+    };
+    const { canceled, filePath } = mainWindow && !mainWindow.isDestroyed()
+      ? await dialog.showSaveDialog(mainWindow, options)
+      : await dialog.showSaveDialog(options);
+    if (canceled || !filePath) return null;
+    target = filePath;
+  }
+  const entries = await collectSupportBundle();
+  fs.writeFileSync(target, createZip(entries));
+  appendLog(`已导出问题反馈包: ${target}（${entries.length} 个文件）`);
+  return target;
+}
+
+// IPC 只响应本应用自己的页面：启动屏 loading.html 与当前管理界面（开发模式下为开发服务器）的源。
+// preload 在主窗口的任何页面中都会注入，窗口若被链接或重定向带到其它站点，那里的脚本同样能调用这些接口，
+// 因此每个处理函数先校验发送方 frame 的 URL
+function isTrustedUrl(rawUrl) {
+  try {
+    const url = new URL(rawUrl);
+    if (url.protocol === 'file:') return path.relative(SPLASH_FILE, fileURLToPath(url)) === '';
+    return url.origin === new URL(getTargetUrl()).origin;
+  } catch {
+    return false;
+  }
+}
+
+function isTrustedSender(event) {
+  return !!event.senderFrame && isTrustedUrl(event.senderFrame.url);
+}
+
+// 注册 invoke 通道：发送方不可信时记录日志并拒绝（渲染层的 invoke 收到错误）
+function handleTrusted(channel, handler) {
+  ipcMain.handle(channel, (event, ...args) => {
+    if (!isTrustedSender(event)) {
+      appendLog(`⚠ 已拒绝来自 ${event.senderFrame ? event.senderFrame.url : '未知页面'} 的 IPC 调用: ${channel}`, 'warn');
+      throw new Error(`IPC ${channel} 不允许从当前页面调用`);
+    }
+    return handler(event, ...args);
+  });
+}
+
+function registerIpcHandlers() {
+  handleTrusted('reload-config', () => reloadConfig());
+  handleTrusted('get-logs', (_e, lines) => getLogs(lines));
+  handleTrusted('open-config-file', () => openConfigFile());
+  handleTrusted('diagnose', () => diagnose());
+  handleTrusted('set-log-streaming', (_e, enabled) => setLogStreaming(enabled));
+  handleTrusted('reset-app-data', (_e, keepConfig) => resetAppData(keepConfig !== false));
+  // 服务运行中返回实际端口（可能因 AUTO_PORT 与配置不同），未运行时返回配置端口
+  handleTrusted('get-port', () => (serverReady ? port : readPortFromConfig(config)));
+  handleTrusted('is-port-available', (_e, targetPort) => checkPortAvailable(targetPort));
+  handleTrusted('get-effective-config', () => getEffectiveConfig());
+  handleTrusted('open-logs-folder', () => openLogsFolder());
+  handleTrusted('reload-window', () => reloadWindow());
+  handleTrusted('restart-server', () => restartServer());
+  handleTrusted('export-support-bundle', () => exportSupportBundle());
+  // preload 同步读取（须在页面脚本之前写入 localStorage），仅向 prepareAutoLogin 记录的本机管理界面源下发
+  ipcMain.on('get-autologin-token', (e, origin) => {
+    e.returnValue = isTrustedSender(e) && autoLogin && origin === autoLogin.origin ? autoLogin.token : null;
+  });
+}
+
+async function bootstrap() {
+  let appDirError = null;
+  try {
+    ensureAppDir();
+  } catch (err) {
+    appDirError = new StartupError('APP_DIR_UNAVAILABLE', `无法创建数据目录 ${LOG_DIR}：${err && err.message ? err.message : err}\n` +
+      '请检查该目录及用户主目录的读写权限后重新启动应用。');
+  }
+  installOutputCapture();
+  config = readConfig();
+  port = readPortFromConfig(config);
+  appendLog('=== AICodeSwitch Electron 启动日志 ===');
+  appendLog(`用户目录: ${HOME.dir}（来源: ${HOME.source}）`);
+  appendLog(activeProfile ? `配置档: ${activeProfile.name}（${activeProfile.path}）` : '配置档: 默认（aicodeswitch.conf）');
+  logConfigSources();
+  appendLog(`配置: ${JSON.stringify(redactConfig(config))}`, 'debug');
+  applyNodeArgs(config);
+
+  createWindow();
+  try {
+    createTray();
   } catch (err) {
-    sendError(`服务已就绪，但无法打开管理界面：${err && err.message ? err.message : err}`);
+    appendLog(`创建托盘图标失败: ${err && err.message ? err.message : err}`, 'warn');
+  }
+  registerIpcHandlers();
+  reportConfigWarnings();
+  if (appDirError) {
+    sendError(appDirError);
+    return;
+  }
+  sendLog('应用已启动');
+  checkRuntimeArch();
+
+  if (/^(0|false|no|off)$/i.test(String(config.AUTO_START || '').trim())) {
+    await runLifecycle('连接已有服务', () => connectWithoutAutoStart());
+  } else {
+    await runLifecycle('启动服务', () => startAndNavigate());
   }
+  bootstrapped = true;
 }
 
 // ── 退出前的优雅关闭 ──────────────────────────────────────────────────
 async function gracefulQuit() {
   if (isQuitting) return;
   isQuitting = true;
+  // app.exit() 不会触发窗口的 close 事件（如 macOS Cmd+Q），在这里补存窗口位置
+  if (mainWindow && !mainWindow.isDestroyed()) saveWindowState(mainWindow);
+  if (watchdogTimer) { clearTimeout(watchdogTimer); watchdogTimer = null; }
+  stopHealthMonitor();
+  healthAgent.destroy();
   appendLog('开始应用退出流程...');
   try {
     if (serverModule && typeof serverModule.gracefulShutdown === 'function') {
       // 触发服务端完整关闭：恢复 Claude/Codex/OpenCode 配置、关闭 DB/日志、释放端口
-      await serverModule.gracefulShutdown('ELECTRON_QUIT');
-    } else if (serverReady) {
-      // 兜底：服务未导出 gracefulShutdown 时走 HTTP /api/shutdown
-      await new Promise((resolve) => {
-        const req = http.request(
-          { hostname: '127.0.0.1', port, path: '/api/shutdown', method: 'POST', timeout: 8000 },
-          (res) => { res.resume(); resolve(); },
-        );
-        req.on('error', () => resolve());
-        req.on('timeout', () => { req.destroy(); resolve(); });
-        req.end();
+      await shutdownWithTimeout(async () => {
+        // 启动进行中退出：先等 start() 收尾（注册好关闭流程），确保它已写入的工具配置能被恢复
+        if (serverStarting) await serverStarting;
+        await shutdownServerModule('ELECTRON_QUIT', serverReady);
       });
+    } else if (serverReady && !serverAttached) {
+      // 兜底：服务未导出 gracefulShutdown 时走 HTTP 关闭端点（SHUTDOWN_ENDPOINT，默认 /api/shutdown）
+      const endpoint = getShutdownEndpoint() || '/api/shutdown';
+      if (await postShutdownEndpoint(endpoint)) appendLog(`服务关闭方式: 关闭端点 ${endpoint}`);
+    }
+  } catch (err) {
+    appendLog(`关闭流程异常: ${err && err.message ? err.message : err}`, 'error');
+  }
+}
+
+// 导航与托盘启停共用 runLifecycle 串行执行，避免与进行中的重启交错；排在启动流程之后时
+// 若启动流程已完成导航则不再重复
+function reopenMainWindow() {
+  createWindow();
+  runLifecycle('打开管理界面', async () => {
+    if (!serverReady) {
+      sendLog('服务未运行，可通过托盘菜单「启动服务」启动');
+      revealMainWindow();
+      return;
     }
+    if (!hasNavigated) await navigateMainWindow();
+  }).catch((err) => appendLog(`打开管理界面失败: ${err && err.message ? err.message : err}`, 'warn'));
+}
+
+// ── 单实例 ────────────────────────────────────────────────────────────
+// 同一时间只允许一个桌面端实例：两个实例会争抢同一端口与 ~/.aicodeswitch 数据。
+// 再次启动时新实例直接退出，由已运行的实例把主窗口拉到前台。
+// --check：安装自检（CI / 打包校验用）。复用 diagnose() 检查运行时、配置、服务入口、数据目录与端口，
+// 结果打印到 stdout，全部通过时退出码 0，否则 1；不创建窗口、不启动服务，也不占用单实例锁
+const CHECK_MODE = process.argv.includes('--check');
+const gotSingleInstanceLock = !CHECK_MODE && app.requestSingleInstanceLock();
+
+async function runSetupCheck() {
+  let ok = false;
+  const lines = ['AI Code Switch 安装自检'];
+  const line = (passed, label, detail) => lines.push(`  [${passed ? 'OK' : 'FAIL'}] ${label}: ${detail}`);
+  try {
+    try { ensureAppDir(); } catch { /* 由 dataDir 检查报告 */ }
+    config = readConfig();
+    port = readPortFromConfig(config);
+    bindHost = resolveBindHost(config);
+    for (const warning of configWarnings) lines.push(`  [WARN] ${warning}`);
+    const report = await diagnose();
+    const fail = (section) => section.error;
+    const { runtime, serverEntry, config: conf, dataDir, configuredPortOwner: owner } = report;
+    line(runtime.ok, '运行时', runtime.ok
+      ? `Electron ${runtime.electron} / Node ${runtime.node} / ${runtime.platform} ${runtime.arch}`
+      : fail(runtime));
+    line(conf.ok, '配置文件', conf.ok
+      ? `${conf.path}${conf.exists ? '' : '（不存在，使用默认值）'}${conf.profile ? `，配置档 ${conf.profile}` : ''}`
+      : fail(conf));
+    line(serverEntry.ok, '服务入口', serverEntry.ok ? serverEntry.path : fail(serverEntry));
+    line(dataDir.ok, '数据目录', dataDir.ok ? `${dataDir.path}（可写）` : fail(dataDir));
+    // 端口被其它程序占用时，AUTO_PORT 开启仍可启动
+    const portOk = owner.ok && (owner.owner === 'free' || (owner.owner === 'foreign' && configFlag(config, 'AUTO_PORT')));
+    line(portOk, `端口 ${port}`, owner.ok ? owner.owner : fail(owner));
+    ok = runtime.ok && conf.ok && serverEntry.ok && dataDir.ok && portOk;
   } catch (err) {
-    appendLog(`关闭流程异常: ${err && err.message ? err.message : err}`);
+    lines.push(`  [FAIL] 自检异常: ${err && err.message ? err.message : err}`);
   }
+  lines.push(ok ? '结果: 通过' : '结果: 未通过');
+  process.stdout.write(`${lines.join('\n')}\n`);
+  healthAgent.destroy();
+  app.exit(ok ? 0 : 1);
+}
+
+function showMainWindow() {
+  if (!mainWindow || mainWindow.isDestroyed()) {
+    // KEEP_ALIVE_ON_CLOSE 关窗后窗口已销毁：重新创建，服务在运行则直接导航到管理界面
+    if (app.isReady() && !isQuitting) reopenMainWindow();
+    return;
+  }
+  if (!windowRevealed) {
+    revealMainWindow();
+    return;
+  }
+  if (mainWindow.isMinimized()) mainWindow.restore();
+  mainWindow.show();
+  mainWindow.focus();
+}
+
+if (CHECK_MODE) {
+  app.whenReady().then(runSetupCheck);
+} else if (!gotSingleInstanceLock) {
+  appendLog('检测到已有 AI Code Switch 实例在运行，本次启动退出并唤起已有窗口');
+  app.quit();
+} else {
+  app.on('second-instance', () => {
+    showMainWindow();
+  });
 }
 
 // ── 应用生命周期 ──────────────────────────────────────────────────────
@@ -296,28 +2742,62 @@ app.on('before-quit', (e) => {
   if (!isQuitting) {
     e.preventDefault();
     gracefulQuit().finally(() => {
-      app.exit(0);
+      app.exit(appExitCode);
     });
   }
 });
 
-app.whenReady().then(() => {
-  bootstrap().catch((err) => {
-    appendLog(`bootstrap 异常: ${err && err.stack ? err.stack : err}`);
+if (gotSingleInstanceLock) {
+  app.whenReady().then(() => {
+    bootstrap().catch((err) => {
+      appendLog(`bootstrap 异常: ${err && err.stack ? err.stack : err}`, 'error');
+    });
   });
-});
+}
+
+// 从终端启动（electron:start / electron:dev）时的 Ctrl+C / SIGTERM：服务端 start() 注册的信号处理器
+// 在内嵌模式下只关闭服务、不退出进程，会残留一个没有服务的窗口；这里统一走 gracefulQuit 后退出。
+// 退出流程进行中再次收到信号则立即强制退出。
+for (const signal of ['SIGINT', 'SIGTERM']) {
+  process.on(signal, () => {
+    if (isQuitting) {
+      appendLog(`退出中再次收到 ${signal}，强制退出`);
+      app.exit(1);
+      return;
+    }
+    appendLog(`收到 ${signal}，开始退出应用`);
+    app.quit();
+  });
+}
 
-// 所有窗口关闭时退出（非 macOS 行为；macOS 由 before-quit 兜底）
+// 所有窗口关闭时退出（非 macOS 行为；macOS 由 before-quit 兜底；KEEP_ALIVE_ON_CLOSE 时保持运行）
 app.on('window-all-closed', () => {
-  if (process.platform !== 'darwin') {
+  if (process.platform !== 'darwin' && !configFlag(config, 'KEEP_ALIVE_ON_CLOSE')) {
     app.quit();
   }
 });
 
-// macOS：点击 dock 图标时重新显示已隐藏的窗口（关窗只是 hide，窗口仍在）
+// macOS：点击 dock 图标时重新显示窗口（关窗只是 hide 时直接显示；KEEP_ALIVE_ON_CLOSE 销毁后重新创建），
+// 若服务处于停止状态则询问是否启动，避免回到界面后只看到启动屏
+let bootstrapped = false;   // 首次启动流程结束前不询问（macOS 首次启动也会触发 activate）
+
+async function offerServerStart() {
+  if (!bootstrapped || isQuitting || serverReady || serverModule || serverStarting) return;
+  const { response } = await showMessageBox({
+    type: 'question',
+    title: 'AI Code Switch',
+    message: '服务未运行',
+    detail: '是否现在启动服务并打开管理界面？',
+    buttons: ['启动服务', '稍后'],
+    defaultId: 0,
+    cancelId: 1,
+  });
+  if (response === 0) runLifecycle('启动服务', () => startAndNavigate());
+}
+
 app.on('activate', () => {
-  if (mainWindow) {
-    mainWindow.show();
-    mainWindow.focus();
+  showMainWindow();
+  if (process.platform === 'darwin') {
+    offerServerStart().catch((err) => appendLog(`启动服务询问失败: ${err && err.message ? err.message : err}`, 'warn'));
   }
 });
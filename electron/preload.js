@@ -1,12 +1,20 @@
 /**
- * Electron preload：在隔离的渲染层（loading.html）暴露受控的 IPC 接收 API。
- * 仅暴露 onStartupLog / onStartupError 两个只读监听器，不开放任何 Node 能力。
+ * Electron preload：在隔离的渲染层（loading.html 与管理界面）暴露受控的 IPC API。
+ * 只暴露启动日志监听器与主进程白名单命令（ipcRenderer.invoke），不开放任何 Node 能力。
  */
 
 'use strict';
 
 const { contextBridge, ipcRenderer } = require('electron');
 
+// AUTH_AUTOLOGIN：主进程只为本机管理界面的源下发登录 token，在页面脚本运行前写入 localStorage
+if (/^https?:$/.test(window.location.protocol)) {
+  try {
+    const token = ipcRenderer.sendSync('get-autologin-token', window.location.origin);
+    if (token) window.localStorage.setItem('auth_token', token);
+  } catch { /* ignore */ }
+}
+
 contextBridge.exposeInMainWorld('aicodeswitch', {
   onStartupLog: (handler) => {
     const listener = (_event, payload) => handler(payload);
@@ -18,4 +26,32 @@ contextBridge.exposeInMainWorld('aicodeswitch', {
     ipcRenderer.on('startup-error', listener);
     return () => ipcRenderer.removeListener('startup-error', listener);
   },
+  onServerStatus: (handler) => {
+    const listener = (_event, payload) => handler(payload);
+    ipcRenderer.on('server-status', listener);
+    return () => ipcRenderer.removeListener('server-status', listener);
+  },
+  onServerLog: (handler) => {
+    const listener = (_event, payload) => handler(payload);
+    ipcRenderer.on('server-log', listener);
+    return () => ipcRenderer.removeListener('server-log', listener);
+  },
+  onRuntimeWarning: (handler) => {
+    const listener = (_event, payload) => handler(payload);
+    ipcRenderer.on('runtime-warning', listener);
+    return () => ipcRenderer.removeListener('runtime-warning', listener);
+  },
+  reloadConfig: () => ipcRenderer.invoke('reload-config'),
+  getLogs: (lines) => ipcRenderer.invoke('get-logs', lines),
+  getPort: () => ipcRenderer.invoke('get-port'),
+  isPortAvailable: (port) => ipcRenderer.invoke('is-port-available', port),
+  getEffectiveConfig: () => ipcRenderer.invoke('get-effective-config'),
+  openConfigFile: () => ipcRenderer.invoke('open-config-file'),
+  openLogsFolder: () => ipcRenderer.invoke('open-logs-folder'),
+  reloadWindow: () => ipcRenderer.invoke('reload-window'),
+  restartServer: () => ipcRenderer.invoke('restart-server'),
+  resetAppData: (keepConfig = true) => ipcRenderer.invoke('reset-app-data', keepConfig),
+  diagnose: () => ipcRenderer.invoke('diagnose'),
+  exportSupportBundle: () => ipcRenderer.invoke('export-support-bundle'),
+  setLogStreaming: (enabled) => ipcRenderer.invoke('set-log-streaming', enabled),
 });
@@ -0,0 +1,72 @@
+'use strict';
+
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { redactConfig, parseConfig, readConfigFile, pickEnvOverrides, mergeConfig } = require('../config');
+
+// 每个用例写到独立的临时目录，结束后删除
+function withConfigFile(t, content) {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'aic-config-'));
+  t.after(() => fs.rmSync(dir, { recursive: true, force: true }));
+  const file = path.join(dir, 'aicodeswitch.conf');
+  fs.writeFileSync(file, content);
+  return file;
+}
+
+test('parses KEY=VALUE lines, skipping comments, blanks and lines without a key', () => {
+  const { values } = parseConfig('# comment\n\nPORT=4567\n=nokey\nnoequals\n  AUTH = secret  \n');
+  assert.deepEqual(values, { PORT: '4567', AUTH: 'secret' });
+});
+
+test('strips a UTF-8 BOM and accepts CRLF line endings', (t) => {
+  const file = withConfigFile(t, '\uFEFFPORT=5000\r\nAUTH=abc\r\n');
+  assert.deepEqual(readConfigFile(file), { PORT: '5000', AUTH: 'abc' });
+});
+
+test('removes matching surrounding quotes only', () => {
+  const { values } = parseConfig(`A="x y"\nB='z'\nC="mismatched'\nD="`);
+  assert.deepEqual(values, { A: 'x y', B: 'z', C: `"mismatched'`, D: '"' });
+});
+
+test('keeps the last duplicate and warns with the value redacted', (t) => {
+  const file = withConfigFile(t, 'PORT=1\nAUTH=first\nPORT=2\nAUTH=second\n');
+  const warnings = [];
+  assert.deepEqual(readConfigFile(file, warnings), { PORT: '2', AUTH: 'second' });
+  assert.deepEqual(warnings, [
+    '配置项 PORT 在 aicodeswitch.conf 中出现了 2 次，以最后一次为准: PORT=2',
+    '配置项 AUTH 在 aicodeswitch.conf 中出现了 2 次，以最后一次为准: AUTH=******',
+  ]);
+});
+
+test('returns an empty config for a missing file', () => {
+  const warnings = [];
+  assert.deepEqual(readConfigFile(path.join(os.tmpdir(), 'aic-missing', 'none.conf'), warnings), {});
+  assert.deepEqual(warnings, []);
+});
+
+test('redacts secret-like keys and ENV_* values', () => {
+  assert.deepEqual(
+    redactConfig({ PORT: '4567', AUTH: 'a', JWT_SECRET: 'b', SKILLSMP_API_KEY: 'c', ENV_FOO: 'd', EMPTY_TOKEN: '' }),
+    { PORT: '4567', AUTH: '******', JWT_SECRET: '******', SKILLSMP_API_KEY: '******', ENV_FOO: '******', EMPTY_TOKEN: '' },
+  );
+});
+
+test('only non-blank environment variables override, trimmed', () => {
+  assert.deepEqual(
+    pickEnvOverrides({ PORT: ' 5000 ', AUTH: '   ', HOST: '0.0.0.0' }, ['PORT', 'AUTH']),
+    { PORT: '5000' },
+  );
+});
+
+test('merges with precedence env > profile > file', () => {
+  const merged = mergeConfig(
+    { PORT: '1', AUTH: 'file', BASE_PATH: '/file', SHOW_WINDOW: 'always' },
+    { PORT: '2', AUTH: 'profile', BASE_PATH: '/profile' },
+    { PORT: '3' },
+  );
+  assert.deepEqual(merged, { PORT: '3', AUTH: 'profile', BASE_PATH: '/profile', SHOW_WINDOW: 'always' });
+  assert.deepEqual(mergeConfig({ PORT: '1' }, null, {}), { PORT: '1' });
+});
@@ -0,0 +1,74 @@
+'use strict';
+
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const {
+  resolveBindHost,
+  toConnectHost,
+  isLoopbackHost,
+  normalizeUrlPath,
+  createEndpoint,
+  endpointUrl,
+  navigationUrl,
+  alternateLoopbackHost,
+} = require('../endpoint');
+
+test('binds 0.0.0.0 only when AUTH is set in the environment or the config', () => {
+  assert.equal(resolveBindHost({}, {}), '127.0.0.1');
+  assert.equal(resolveBindHost({ AUTH: '  ' }, {}), '127.0.0.1');
+  assert.equal(resolveBindHost({ AUTH: 'secret' }, {}), '0.0.0.0');
+  assert.equal(resolveBindHost({}, { AUTH: 'secret' }), '0.0.0.0');
+});
+
+test('maps wildcard bind hosts to a connectable loopback address', () => {
+  assert.equal(toConnectHost('0.0.0.0'), '127.0.0.1');
+  assert.equal(toConnectHost(''), '127.0.0.1');
+  assert.equal(toConnectHost('::'), '::1');
+  assert.equal(toConnectHost('[::]'), '::1');
+  assert.equal(toConnectHost('[::1]'), '::1');
+  assert.equal(toConnectHost('127.0.0.1'), '127.0.0.1');
+  assert.equal(toConnectHost('localhost'), 'localhost');
+});
+
+test('recognises loopback hosts', () => {
+  for (const host of ['127.0.0.1', '127.1.2.3', '::1', '[::1]', 'localhost', 'LOCALHOST']) {
+    assert.equal(isLoopbackHost(host), true, host);
+  }
+  for (const host of ['0.0.0.0', '192.168.1.10', '::', 'example.com', '']) {
+    assert.equal(isLoopbackHost(host), false, host);
+  }
+});
+
+test('normalizes URL path settings', () => {
+  assert.equal(normalizeUrlPath(''), '');
+  assert.equal(normalizeUrlPath('/'), '');
+  assert.equal(normalizeUrlPath(' app/ '), '/app');
+  assert.equal(normalizeUrlPath('//a/b//'), '/a/b');
+});
+
+test('builds valid URLs for IPv4, IPv6 and host names', () => {
+  assert.equal(endpointUrl(createEndpoint('127.0.0.1', 4567), '/health'), 'http://127.0.0.1:4567/health');
+  assert.equal(endpointUrl(createEndpoint('::1', 4567), '/health'), 'http://[::1]:4567/health');
+  assert.equal(endpointUrl(createEndpoint('localhost', 4567)), 'http://localhost:4567/');
+});
+
+test('health, request and navigation URLs share one origin; only navigation gets BASE_PATH', () => {
+  for (const host of ['127.0.0.1', '::1', 'localhost']) {
+    const endpoint = createEndpoint(host, 4567, '/app/');
+    const health = new URL(endpointUrl(endpoint, '/health'));
+    const shutdown = new URL(endpointUrl(endpoint, '/api/shutdown'));
+    const navigation = new URL(navigationUrl(endpoint));
+    assert.equal(health.origin, navigation.origin);
+    assert.equal(shutdown.origin, navigation.origin);
+    assert.equal(health.pathname, '/health');
+    assert.equal(navigation.pathname, '/app/');
+  }
+  assert.equal(navigationUrl(createEndpoint('127.0.0.1', 4567, '')), 'http://127.0.0.1:4567/');
+});
+
+test('switches between the IPv4 and IPv6 loopback addresses', () => {
+  assert.equal(alternateLoopbackHost('::1'), '127.0.0.1');
+  assert.equal(alternateLoopbackHost('127.0.0.1'), '::1');
+  assert.equal(alternateLoopbackHost('localhost'), null);
+  assert.equal(alternateLoopbackHost('192.168.1.10'), null);
+});
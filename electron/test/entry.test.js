@@ -0,0 +1,42 @@
+'use strict';
+
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { serverEntryProblem } = require('../entry');
+
+// 在独立的临时目录下创建文件，结束后删除
+function tempDir(t) {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'aic-entry-'));
+  t.after(() => fs.rmSync(dir, { recursive: true, force: true }));
+  return dir;
+}
+
+test('accepts .js, .mjs and .cjs files regardless of case', (t) => {
+  const dir = tempDir(t);
+  for (const name of ['main.js', 'main.mjs', 'main.cjs', 'MAIN.JS']) {
+    const file = path.join(dir, name);
+    fs.writeFileSync(file, '');
+    assert.equal(serverEntryProblem(file, '服务入口'), null, name);
+  }
+});
+
+test('rejects a directory', (t) => {
+  const dir = tempDir(t);
+  const problem = serverEntryProblem(dir, 'SERVER_ENTRY ');
+  assert.match(problem, /^SERVER_ENTRY 指向的是目录而不是 JS 文件/);
+  assert.ok(problem.includes(dir));
+});
+
+test('rejects files with another extension or none, naming the extension', (t) => {
+  const dir = tempDir(t);
+  const ts = path.join(dir, 'main.ts');
+  const bare = path.join(dir, 'main');
+  fs.writeFileSync(ts, '');
+  fs.writeFileSync(bare, '');
+  assert.match(serverEntryProblem(ts, '服务入口'), /^服务入口不是 JS 文件（扩展名 \.ts）/);
+  assert.match(serverEntryProblem(bare, '服务入口'), /（扩展名 无）/);
+  assert.match(serverEntryProblem(bare, '服务入口'), /\.js \/ \.mjs \/ \.cjs/);
+});
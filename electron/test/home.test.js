@@ -0,0 +1,51 @@
+'use strict';
+
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const path = require('path');
+const { resolveHomeDir } = require('../home');
+
+// 只把 existing 中列出的目录视为存在，环境变量全部由用例注入
+function resolve({ env = {}, homedir = () => '', electronHome = () => '', existing = [] }) {
+  return resolveHomeDir({ env, homedir, electronHome, exists: (dir) => existing.includes(dir) });
+}
+
+test('prefers os.homedir() when it exists', () => {
+  const home = resolve({
+    env: { HOME: '/env/home' },
+    homedir: () => '/os/home',
+    existing: ['/os/home', '/env/home'],
+  });
+  assert.deepEqual(home, { dir: '/os/home', source: 'os.homedir()' });
+});
+
+test('falls back through HOME and USERPROFILE', () => {
+  assert.deepEqual(
+    resolve({ env: { HOME: '/env/home' }, homedir: () => '/missing', existing: ['/env/home'] }),
+    { dir: '/env/home', source: 'HOME' },
+  );
+  assert.deepEqual(
+    resolve({ env: { HOME: '/missing', USERPROFILE: 'C:\\Users\\me' }, existing: ['C:\\Users\\me'] }),
+    { dir: 'C:\\Users\\me', source: 'USERPROFILE' },
+  );
+});
+
+test('joins HOMEDRIVE and HOMEPATH when only those are set', () => {
+  const expected = path.join('C:', '\\Users\\me');
+  const home = resolve({ env: { HOMEDRIVE: 'C:', HOMEPATH: '\\Users\\me' }, existing: [expected] });
+  assert.deepEqual(home, { dir: expected, source: 'HOMEDRIVE+HOMEPATH' });
+  assert.equal(resolve({ env: { HOMEDRIVE: 'C:' }, existing: ['C:'] }), null);
+});
+
+test('uses the Electron home path last and skips sources that throw', () => {
+  const home = resolve({
+    homedir: () => { throw new Error('uv_os_homedir failed'); },
+    electronHome: () => '/electron/home',
+    existing: ['/electron/home'],
+  });
+  assert.deepEqual(home, { dir: '/electron/home', source: "app.getPath('home')" });
+});
+
+test('returns null instead of a temporary directory when nothing exists', () => {
+  assert.equal(resolve({ env: { HOME: '/missing', USERPROFILE: '/missing2' }, homedir: () => '/missing3' }), null);
+});
@@ -0,0 +1,57 @@
+'use strict';
+
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const zlib = require('zlib');
+const { dosDateTime, createZip } = require('../zip');
+
+// 按中央目录读回所有条目，校验偏移、CRC 与解压后的内容
+function readZip(buf) {
+  const end = buf.length - 22;
+  assert.equal(buf.readUInt32LE(end), 0x06054b50);
+  const count = buf.readUInt16LE(end + 10);
+  let pos = buf.readUInt32LE(end + 16);
+  const entries = [];
+  for (let i = 0; i < count; i++) {
+    assert.equal(buf.readUInt32LE(pos), 0x02014b50);
+    const crc = buf.readUInt32LE(pos + 16);
+    const size = buf.readUInt32LE(pos + 20);
+    const nameLength = buf.readUInt16LE(pos + 28);
+    const localOffset = buf.readUInt32LE(pos + 42);
+    const name = buf.subarray(pos + 46, pos + 46 + nameLength).toString('utf-8');
+    assert.equal(buf.readUInt32LE(localOffset), 0x04034b50);
+    const dataStart = localOffset + 30 + buf.readUInt16LE(localOffset + 26);
+    const data = zlib.inflateRawSync(buf.subarray(dataStart, dataStart + size));
+    assert.equal(zlib.crc32(data), crc);
+    entries.push({ name, data: data.toString('utf-8') });
+    pos += 46 + nameLength;
+  }
+  return entries;
+}
+
+test('round-trips entries through the central directory', () => {
+  const zip = createZip([
+    { name: 'system.txt', data: Buffer.from('版本: 1.0\n') },
+    { name: 'logs/server.log', data: Buffer.from('line 1\nline 2\n'.repeat(100)) },
+  ]);
+  assert.deepEqual(readZip(zip), [
+    { name: 'system.txt', data: '版本: 1.0\n' },
+    { name: 'logs/server.log', data: 'line 1\nline 2\n'.repeat(100) },
+  ]);
+});
+
+test('writes an empty archive when there are no entries', () => {
+  const zip = createZip([]);
+  assert.equal(zip.length, 22);
+  assert.deepEqual(readZip(zip), []);
+});
+
+test('encodes MS-DOS date and time with 2-second precision', () => {
+  const { time, date } = dosDateTime(new Date(2026, 9, 15, 13, 45, 59));
+  assert.equal(time >> 11, 13);
+  assert.equal((time >> 5) & 0x3f, 45);
+  assert.equal((time & 0x1f) * 2, 58);
+  assert.equal((date >> 9) + 1980, 2026);
+  assert.equal((date >> 5) & 0x0f, 10);
+  assert.equal(date & 0x1f, 15);
+});
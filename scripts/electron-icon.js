@@ -15,6 +15,7 @@ const path = require('path');
 const SRC = path.resolve(__dirname, '..', 'src', 'ui', 'assets', 'logo.png');
 const BUILD_DIR = path.resolve(__dirname, '..', 'build');
 const DEST = path.join(BUILD_DIR, 'icon.png');
+const TRAY_DEST = path.resolve(__dirname, '..', 'electron', 'icon.png');
 
 if (!fs.existsSync(SRC)) {
   console.error(`[electron-icon] 源文件不存在: ${SRC}`);
@@ -27,4 +28,8 @@ if (!fs.existsSync(BUILD_DIR)) {
 
 fs.copyFileSync(SRC, DEST);
 console.log(`[electron-icon] 已生成 ${path.relative(path.resolve(__dirname, '..'), DEST)}`);
+
+// 托盘/窗口运行时图标：build/ 不随应用打包，额外复制一份到 electron/（随 electron/** 打包）
+fs.copyFileSync(SRC, TRAY_DEST);
+console.log(`[electron-icon] 已生成 ${path.relative(path.resolve(__dirname, '..'), TRAY_DEST)}`);
 console.log('[electron-icon] electron-builder 会在打包时据此自动生成 .ico/.icns。');
@@ -1633,7 +1633,8 @@ const listInstalledSkills = (): InstalledSkill[] => {
 const registerRoutes = async (dbManager: FileSystemDatabaseManager, proxyServer: ProxyServer) => {
   updateProxyConfig(dbManager.getConfig());
 
-  app.get('/health', (_req, res) => res.json({ status: 'ok' }));
+  // app 字段是桌面端识别「端口上是不是 AI Code Switch 服务」的标记，勿改名
+  app.get('/health', (_req, res) => res.json({ status: 'ok', app: 'aicodeswitch' }));
 
   // 数据就绪验证端点（供桌面端 Electron 启动阶段确认后端完全可用）
   app.get('/api/ready', (_req, res) => {
@@ -4720,6 +4721,8 @@ const IN_PROCESS = process.env.AIC_IN_PROCESS === '1';
 
 // 保存当前服务实例的优雅关闭函数，供宿主（Electron 主进程）在退出前显式调用。
 let _gracefulShutdown: ((signal: string) => Promise<void>) | null = null;
+// 注销 start() 注册的信号监听器（SIGINT / SIGTERM），由 dispose() 调用
+let _removeSignalHandlers: (() => void) | null = null;
 
 /**
  * 供宿主进程调用的优雅关闭入口。
@@ -4836,7 +4839,10 @@ export const start = async () => {
       isPortUsable = await checkPortUsable(port);
     }
     if (!isPortUsable) {
-      console.error(`端口 ${port} 在 ${PORT_WAIT_TIMEOUT / 1000}s 后仍被占用，无法启动服务。请执行 aicos stop 后重启。`);
+      const message = `端口 ${port} 在 ${PORT_WAIT_TIMEOUT / 1000}s 后仍被占用，无法启动服务。请执行 aicos stop 后重启。`;
+      console.error(message);
+      // 内嵌模式下 process.exit 会连同宿主（Electron）一起退出，改为让 start() reject 交由宿主报错
+      if (IN_PROCESS) throw new Error(message);
       process.exit(1);
     }
     console.log(`端口 ${port} 已释放，继续启动...`);
@@ -4862,9 +4868,20 @@ export const start = async () => {
     } else {
       console.error('[Server] 监听失败:', err);
     }
-    setImmediate(() => process.exit(1));
+    // 内嵌模式：不退出宿主进程，监听失败经下方 listening 的 reject 由 start() 抛给宿主
+    if (!IN_PROCESS) {
+      setImmediate(() => process.exit(1));
+    }
   });
 
+  // 内嵌模式下 start() 在真正开始监听后才 resolve，监听失败则 reject，宿主可立即感知而不必等健康检查超时
+  const listening = IN_PROCESS
+    ? new Promise<void>((resolve, reject) => {
+      server.once('listening', () => resolve());
+      server.once('error', reject);
+    })
+    : null;
+
   // 设置黑名单检查函数，用于在规则状态同步时检查黑名单是否已过期
   rulesStatusBroadcaster.setBlacklistChecker(async (serviceId, routeId, contentType) => {
     // 检查服务��否在黑名单中
@@ -4971,8 +4988,14 @@ export const start = async () => {
   // 注册到模块级句柄，供宿主在退出前显式触发完整关闭流程
   _gracefulShutdown = shutdown;
 
-  process.on('SIGINT', () => { void shutdown('SIGINT'); });
-  process.on('SIGTERM', () => { void shutdown('SIGTERM'); });
+  const onSigint = () => { void shutdown('SIGINT'); };
+  const onSigterm = () => { void shutdown('SIGTERM'); };
+  process.on('SIGINT', onSigint);
+  process.on('SIGTERM', onSigterm);
+  _removeSignalHandlers = () => {
+    process.off('SIGINT', onSigint);
+    process.off('SIGTERM', onSigterm);
+  };
 
   // 优雅关闭端点（供 Electron 等外部调用者触发服务端完整清理流程）
   // 放在 shutdown 定义之后注册，确保闭包可引用
@@ -4980,27 +5003,50 @@ export const start = async () => {
     res.json({ success: true });
     setImmediate(() => { void shutdown('HTTP_SHUTDOWN'); });
   }));
+
+  if (listening) {
+    await listening;
+  }
 };
 
 // 全局未捕获异常处理 - 防止服务崩溃
-process.on('uncaughtException', (error: Error) => {
+const onUncaughtException = (error: Error) => {
   console.error('[Uncaught Exception] 服务遇到未捕获的异常:', error);
   console.error('[Uncaught Exception] 堆栈信息:', error.stack);
   // 启动阶段（listen 之前）的异常通常是致命的（依赖加载失败、初始化崩溃等），
   // 静默吞掉会导致"进程在但不 listen"，桌面端只能干等超时；此时退出让上层重新探测/诊断。
-  if (!listenReady) {
+  // 内嵌模式下退出会带走宿主进程，仅记录，由宿主的启动流程报错。
+  if (!listenReady && !IN_PROCESS) {
     console.error('[Uncaught Exception] 发生在服务监听之前，退出进程');
     process.exit(1);
   }
-});
+};
 
-process.on('unhandledRejection', (reason: unknown) => {
+const onUnhandledRejection = (reason: unknown) => {
   console.error('[Unhandled Rejection] 服务遇到未处理的 Promise 拒绝:', reason);
-  if (!listenReady) {
+  if (!listenReady && !IN_PROCESS) {
     console.error('[Unhandled Rejection] 发生在服务监听之前，退出进程');
     process.exit(1);
   }
-});
+};
+
+process.on('uncaughtException', onUncaughtException);
+process.on('unhandledRejection', onUnhandledRejection);
+
+/**
+ * 供宿主在 gracefulShutdown 之后、丢弃本模块之前调用：注销本模块注册的全部 process 级监听器
+ * （未捕获异常 / 信号）。宿主每次重启服务都会重新 require 入口，不注销则监听器不断叠加，
+ * 旧服务实例（数据库、express app）也会被监听器闭包一直引用而无法回收。
+ */
+export const dispose = (): void => {
+  if (_removeSignalHandlers) {
+    _removeSignalHandlers();
+    _removeSignalHandlers = null;
+  }
+  _gracefulShutdown = null;
+  process.off('uncaughtException', onUncaughtException);
+  process.off('unhandledRejection', onUnhandledRejection);
+};
 
 // 仅在被直接运行时（如 aicos start → node dist/server/main.js）自动启动；
 // 被 Electron 主进程 require 时（require.main !== module 或 IN_PROCESS）由宿主显式调用 start()。